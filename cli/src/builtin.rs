@@ -0,0 +1,43 @@
+use std::io;
+
+use crate::{circle, projectile, scene};
+
+// The exercise scenes from the book's chapters, each of which renders itself
+// into ./images.
+pub struct Builtin {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub draw: fn() -> io::Result<()>,
+}
+
+pub const BUILTINS: [Builtin; 5] = [
+    Builtin {
+        name: "chapter-2",
+        description: "projectile trajectory plotted on a canvas",
+        draw: projectile::run_simulation,
+    },
+    Builtin {
+        name: "chapter-4",
+        description: "clock face drawn with rotations",
+        draw: circle::draw_chapter_4_exercise,
+    },
+    Builtin {
+        name: "chapter-5",
+        description: "a shaded sphere traced pixel by pixel",
+        draw: circle::draw_chapter_5_exercise,
+    },
+    Builtin {
+        name: "chapter-7",
+        description: "three spheres in a room made of flattened spheres",
+        draw: scene::draw_chapter_7_exercise,
+    },
+    Builtin {
+        name: "chapter-9",
+        description: "patterned planes with reflective and refractive spheres",
+        draw: scene::draw_chapter_9_exercise,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|b| b.name == name)
+}
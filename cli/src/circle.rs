@@ -1,5 +1,5 @@
 #![allow(dead_code)]
-use ray_tracer::canvas::{canvas, Dimensions, ImageType};
+use ray_tracer::canvas::{canvas, Dimensions};
 use ray_tracer::color::color;
 use ray_tracer::light::{lighting, point_light, PointLight};
 use ray_tracer::ray::{intersect, ray};
@@ -7,8 +7,9 @@ use ray_tracer::shapes::object::Object;
 use ray_tracer::tuple::*;
 use ray_tracer::{material::*, transforms::*};
 use std::f64::consts::PI;
+use std::io;
 
-pub fn draw_chapter_4_exercise() {
+pub fn draw_chapter_4_exercise() -> io::Result<()> {
     let mut c = canvas(1000, 1000);
     let Dimensions { width, height } = c.dimensions;
 
@@ -22,7 +23,7 @@ pub fn draw_chapter_4_exercise() {
         c.write_pixel(x, y, color(0.9, 0.9, 0.9)).unwrap();
     }
 
-    c.save(ImageType::PPM, "chapter4");
+    c.save("images/chapter4.ppm")
 }
 
 pub fn create_sphere() -> Object {
@@ -44,7 +45,7 @@ pub fn create_light() -> PointLight {
     let light_color = color(1., 1., 1.);
     return point_light(position, light_color);
 }
-pub fn draw_chapter_5_exercise() {
+pub fn draw_chapter_5_exercise() -> io::Result<()> {
     let ray_origin = point(0., 0., -5.);
     let wall_z = 10.;
 
@@ -85,5 +86,5 @@ pub fn draw_chapter_5_exercise() {
         }
     }
 
-    c.save(ImageType::PPM, "chapter6-refactored");
+    c.save("images/chapter6-refactored.ppm")
 }
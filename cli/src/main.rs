@@ -1,24 +1,61 @@
+mod builtin;
 mod circle;
 mod projectile;
+mod render;
 mod scene;
+use clap::{Parser, Subcommand};
+use std::process::ExitCode;
 use std::time::Instant;
 
-fn main() {
-    let start = Instant::now();
-    // // Chapter 2 Exercise
-    // projectile::draw_chapter_2_exercise();
+#[derive(Parser)]
+#[command(about = "Renders scenes with the ray tracer")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
 
-    // Chapter 4 Exercise
-    // circle::draw_chapter_4_exercise();
+#[derive(Subcommand)]
+enum Command {
+    /// Render a YAML scene file
+    Render(render::RenderArgs),
+    /// List the built-in chapter scenes
+    List,
+    /// Render one of the built-in chapter scenes into ./images
+    RenderBuiltin {
+        /// Name as shown by `list`
+        name: String,
+    },
+}
 
-    // Chapter 5 Exercise
-    // circle::draw_chapter_5_exercise();
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let start = Instant::now();
 
-    // Chapter 7 Exercise
-    // scene::draw_chapter_7_exercise();
+    let result = match cli.command {
+        Command::Render(args) => render::render_scene_file(&args),
+        Command::List => {
+            for b in builtin::BUILTINS.iter() {
+                println!("{:<10} {}", b.name, b.description);
+            }
+            return ExitCode::SUCCESS;
+        }
+        Command::RenderBuiltin { name } => match builtin::find(&name) {
+            Some(b) => (b.draw)().map_err(|e| e.to_string()),
+            None => Err(format!(
+                "unknown built-in scene '{}', see `list` for the choices",
+                name
+            )),
+        },
+    };
 
-    // Chapter 9 Exercise
-    scene::draw_chapter_9_exercise();
-    let duration = start.elapsed();
-    println!("Rendering duration: {:?}", duration);
+    return match result {
+        Ok(()) => {
+            println!("Rendering duration: {:?}", start.elapsed());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    };
 }
@@ -1,8 +1,10 @@
 #![allow(dead_code)]
 
-use ray_tracer::canvas::{canvas, ImageType};
+use ray_tracer::canvas::canvas;
 use ray_tracer::color::color;
 use ray_tracer::tuple::*;
+use std::io;
+
 struct Projectile {
     position: Tuple,
     velocity: Tuple,
@@ -16,7 +18,7 @@ fn tick(p: &mut Projectile, e: &Environment) {
     p.velocity = p.velocity + e.gravity + e.wind;
 }
 
-pub fn run_simulation() {
+pub fn run_simulation() -> io::Result<()> {
     let mut c = canvas(900, 500);
     let mut projectile = Projectile {
         position: point(0., 1., 0.),
@@ -33,5 +35,5 @@ pub fn run_simulation() {
         c.write_pixel(x, y, color(0.9, 0.5, 0.)).unwrap();
     }
 
-    c.save(ImageType::PPM, "chapter2");
+    c.save("images/chapter2.ppm")
 }
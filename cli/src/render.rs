@@ -0,0 +1,242 @@
+use std::path::{Path, PathBuf};
+
+use clap::{builder::RangedU64ValueParser, Args, ValueEnum};
+use ray_tracer::{
+    aov::{render_with_aovs, Aovs},
+    camera::{camera, render_parallelized},
+    canvas::{Canvas, ImageType, Transfer},
+    diagnostics::has_errors,
+    tonemap::ToneMap,
+    yaml::load_scene,
+};
+
+#[derive(Copy, Clone, ValueEnum)]
+pub enum Format {
+    /// ASCII P3 PPM with linear values, as in the book
+    Ppm,
+    /// Binary P6 PPM
+    PpmBinary,
+    Png,
+    /// PNG with 16 bits per channel
+    Png16,
+    /// Portable float map
+    Pfm,
+    /// Radiance RGBE
+    Hdr,
+    /// OpenEXR with 32-bit float channels
+    Exr,
+}
+
+impl Format {
+    fn image_type(&self) -> ImageType {
+        match self {
+            Format::Ppm => ImageType::PPM,
+            Format::PpmBinary => ImageType::PPMBinary,
+            Format::Png => ImageType::PNG,
+            Format::Png16 => ImageType::PNG16,
+            Format::Pfm => ImageType::PFM,
+            Format::Hdr => ImageType::HDR,
+            Format::Exr => ImageType::EXR,
+        }
+    }
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+pub enum ToneMapper {
+    Clamp,
+    Reinhard,
+    Aces,
+}
+
+impl ToneMapper {
+    fn tone_map(&self) -> ToneMap {
+        match self {
+            ToneMapper::Clamp => ToneMap::Clamp,
+            ToneMapper::Reinhard => ToneMap::Reinhard,
+            ToneMapper::Aces => ToneMap::AcesFilmic,
+        }
+    }
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+pub enum Aov {
+    /// Distance from the camera to the hit
+    Depth,
+    /// World space surface normal
+    Normal,
+    /// Material color before lighting
+    Albedo,
+    /// Which object was hit
+    ObjectId,
+}
+
+impl Aov {
+    fn name(&self) -> &'static str {
+        match self {
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::Albedo => "albedo",
+            Aov::ObjectId => "object-id",
+        }
+    }
+
+    // Float formats get the raw values. Integer formats only hold 0..1, so
+    // depth and normals are remapped and objects get a color each.
+    fn image(&self, aovs: &Aovs, image_type: ImageType) -> Canvas {
+        let raw = image_type.is_float();
+        match self {
+            Aov::Depth if raw => aovs.depth.clone(),
+            Aov::Depth => aovs.normalized_depth(),
+            Aov::Normal if raw => aovs.normal.clone(),
+            Aov::Normal => aovs.normal_colors(),
+            Aov::Albedo => aovs.albedo.clone(),
+            Aov::ObjectId if raw => aovs.object_index(),
+            Aov::ObjectId => aovs.object_colors(),
+        }
+    }
+
+    // Only albedo is a color; the rest are data, which sRGB encoding in
+    // the integer formats would distort.
+    fn transfer(&self) -> Transfer {
+        match self {
+            Aov::Albedo => Transfer::Srgb,
+            _ => Transfer::Linear,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct RenderArgs {
+    /// YAML scene file to render
+    pub scene: PathBuf,
+
+    /// Image width in pixels, overriding the scene's camera
+    #[arg(long, value_parser = clap::value_parser!(i16).range(1..))]
+    pub width: Option<i16>,
+
+    /// Image height in pixels, overriding the scene's camera
+    #[arg(long, value_parser = clap::value_parser!(i16).range(1..))]
+    pub height: Option<i16>,
+
+    /// Field of view in radians, overriding the scene's camera
+    #[arg(long)]
+    pub fov: Option<f64>,
+
+    /// Where to write the image [default: images/<scene name>.<format>]
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+
+    /// Image format [default: from the output's extension, else ppm-binary]
+    #[arg(long, value_enum)]
+    pub format: Option<Format>,
+
+    /// Rays averaged per pixel
+    #[arg(long, default_value_t = 1, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub samples: usize,
+
+    /// Render threads [default: one per CPU]
+    #[arg(long)]
+    pub threads: Option<usize>,
+
+    /// Exposure adjustment in stops, applied before tone mapping
+    #[arg(long, default_value_t = 0., allow_negative_numbers = true)]
+    pub exposure: f64,
+
+    /// Tone mapping operator [default: none, integer formats clamp]
+    #[arg(long, value_enum)]
+    pub tone_map: Option<ToneMapper>,
+
+    /// Extra buffers to write next to the image, as <name>.<aov>.<ext>
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub aov: Vec<Aov>,
+}
+
+pub fn render_scene_file(args: &RenderArgs) -> Result<(), String> {
+    let path = args.scene.display();
+    let mut scene = load_scene(&args.scene).map_err(|e| format!("{}: {}", path, e))?;
+
+    let diagnostics = scene.world.validate();
+    for diagnostic in &diagnostics {
+        eprintln!("{}: {}", path, diagnostic);
+    }
+    if has_errors(&diagnostics) {
+        return Err(format!("{}: not rendering a scene with errors", path));
+    }
+
+    if args.width.is_some() || args.height.is_some() || args.fov.is_some() {
+        let mut resized = camera(
+            args.width.unwrap_or(scene.camera.hsize),
+            args.height.unwrap_or(scene.camera.vsize),
+            args.fov.unwrap_or(scene.camera.field_of_view),
+        );
+        resized.transform = scene.camera.transform.clone();
+        scene.camera = resized;
+    }
+    scene.camera.samples = args.samples;
+
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .map_err(|e| e.to_string())?;
+    }
+
+    let image_type = match (args.format, &args.output) {
+        (Some(format), _) => format.image_type(),
+        (None, Some(output)) => ImageType::from_path(output).map_err(|e| e.to_string())?,
+        (None, None) => ImageType::PPMBinary,
+    };
+    let output = match &args.output {
+        Some(output) => output.clone(),
+        None => {
+            let name = args
+                .scene
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| "scene".to_string());
+            PathBuf::from("images").join(format!("{}.{}", name, image_type.extension()))
+        }
+    };
+
+    let (mut c, aovs) = if args.aov.is_empty() {
+        (render_parallelized(scene.camera, scene.world), None)
+    } else {
+        let (c, aovs) = render_with_aovs(scene.camera, scene.world);
+        (c, Some(aovs))
+    };
+    let mut operators = vec![];
+    if args.exposure != 0. {
+        operators.push(ToneMap::Exposure(args.exposure));
+    }
+    if let Some(mapper) = args.tone_map {
+        operators.push(mapper.tone_map());
+    }
+    c.tone_map(&operators);
+    c.save_as(&output, image_type)
+        .map_err(|e| format!("{}: {}", output.display(), e))?;
+    println!("Wrote {}", output.display());
+
+    if let Some(aovs) = aovs {
+        for aov in &args.aov {
+            let path = aov_path(&output, aov.name());
+            aov.image(&aovs, image_type)
+                .save_with(&path, image_type, aov.transfer())
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            println!("Wrote {}", path.display());
+        }
+    }
+    return Ok(());
+}
+
+// images/scene.png -> images/scene.depth.png
+fn aov_path(output: &Path, name: &str) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let file = match output.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, name, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, name),
+    };
+    return output.with_file_name(file);
+}
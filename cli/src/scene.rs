@@ -1,6 +1,8 @@
 #![allow(dead_code)]
 // use rand::prelude::*;
 use std::f64::consts::PI;
+use std::io;
+use std::sync::Arc;
 
 use ray_tracer::{
     camera::{camera, render, render_parallelized, view_transform},
@@ -14,7 +16,7 @@ use ray_tracer::{
     world::world,
 };
 
-pub fn draw_chapter_7_exercise() {
+pub fn draw_chapter_7_exercise() -> io::Result<()> {
     let mut floor = Object::new_sphere();
     floor.transform = scaling(10., 0.01, 10.);
     floor.material.color = color(1., 0.9, 0.9);
@@ -65,10 +67,10 @@ pub fn draw_chapter_7_exercise() {
 
     let c = render(camera, w);
 
-    c.save(ray_tracer::canvas::ImageType::PPM, "chapter8");
+    c.save("images/chapter8.ppm")
 }
 
-pub fn draw_chapter_9_exercise() {
+pub fn draw_chapter_9_exercise() -> io::Result<()> {
     let mut floor = Object::new_plane();
     // floor.transform = scaling(10., 10., 10.);
     // floor.material.color = color(1., 0.9, 0.9);
@@ -76,7 +78,7 @@ pub fn draw_chapter_9_exercise() {
     {
         let mut pattern = gradient_pattern(color(0.5, 0.2, 0.9), color(0.33, 0.44, 0.99));
         pattern.transform = translation(0., 0., 5.) * scaling(10., 10., 10.) * rotation_y(PI / 2.);
-        floor.material.pattern = Some(Box::new(pattern));
+        floor.material.pattern = Some(Arc::new(pattern));
         floor.material.reflective = 0.0;
     }
 
@@ -88,7 +90,7 @@ pub fn draw_chapter_9_exercise() {
     {
         let mut pattern = stripe_pattern(color(0.91, 0.4, 0.2), color(0.2, 0.54, 0.80));
         pattern.transform = scaling(1.0, 1.0, 1.) * rotation_x(PI);
-        back_wall.material.pattern = Some(Box::new(pattern));
+        back_wall.material.pattern = Some(Arc::new(pattern));
     }
 
     let mut front_wall = Object::new_plane();
@@ -153,8 +155,5 @@ pub fn draw_chapter_9_exercise() {
     // let c = render(camera, w);
     let c = render_parallelized(camera, w);
 
-    c.save(
-        ray_tracer::canvas::ImageType::PPM,
-        "chapter11-refraction-exploration",
-    );
+    c.save("images/chapter11-refraction-exploration.ppm")
 }
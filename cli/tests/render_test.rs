@@ -0,0 +1,103 @@
+use std::fs;
+use std::process::Command;
+
+use ray_tracer::{aov::render_with_aovs, camera::camera, yaml::load_scene};
+
+fn render(args: &[&str]) -> std::process::Output {
+    let scene = concat!(env!("CARGO_MANIFEST_DIR"), "/scenes/reflections.yaml");
+    return Command::new(env!("CARGO_BIN_EXE_cli"))
+        .arg("render")
+        .arg(scene)
+        .args(args)
+        .output()
+        .unwrap();
+}
+
+#[test]
+fn image_sizes_below_one_pixel_are_rejected() {
+    for args in [
+        ["--width", "0"],
+        ["--height", "0"],
+        ["--width=-4", "--height=2"],
+    ] {
+        let output = render(&args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert_eq!(output.status.code(), Some(2), "{:?}: {}", args, stderr);
+        assert!(
+            stderr.contains("is not in 1..=32767"),
+            "{:?}: {}",
+            args,
+            stderr
+        );
+    }
+}
+
+#[test]
+fn zero_samples_are_rejected() {
+    let output = render(&["--samples", "0"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(2), "{}", stderr);
+    assert!(stderr.contains("'--samples <SAMPLES>'"), "{}", stderr);
+}
+
+#[test]
+fn data_aovs_are_written_linearly_and_albedo_as_srgb() {
+    let dir = std::env::temp_dir().join("ray_tracer_cli_aov_test");
+    let output = dir.join("reflections.ppm");
+    let result = render(&[
+        "--width=8",
+        "--height=6",
+        "--format=ppm-binary",
+        "--aov=normal",
+        "--aov=albedo",
+        "-o",
+        output.to_str().unwrap(),
+    ]);
+    assert!(result.status.success(), "{:?}", result);
+
+    let scene = load_scene(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/scenes/reflections.yaml"
+    ))
+    .unwrap();
+    let mut resized = camera(8, 6, scene.camera.field_of_view);
+    resized.transform = scene.camera.transform.clone();
+    let (_, aovs) = render_with_aovs(resized, scene.world);
+
+    let header = b"P6\n8 6\n255\n".to_vec();
+    let linear: Vec<u8> = aovs
+        .normal_colors()
+        .pixels
+        .iter()
+        .map(|c| (255. * c).round() as u8)
+        .collect();
+    assert_eq!(
+        fs::read(dir.join("reflections.normal.ppm")).unwrap(),
+        [header, linear].concat()
+    );
+    assert_eq!(
+        fs::read(dir.join("reflections.albedo.ppm")).unwrap(),
+        aovs.albedo.canvas_to_ppm_binary()
+    );
+}
+
+#[test]
+fn ppm_output_is_srgb_encoded_binary_unless_p3_is_asked_for() {
+    let dir = std::env::temp_dir().join("ray_tracer_cli_ppm_test");
+    let output = dir.join("reflections.ppm");
+    let path = output.to_str().unwrap();
+
+    assert!(render(&["--width=4", "--height=3", "-o", path])
+        .status
+        .success());
+    assert!(fs::read(&output).unwrap().starts_with(b"P6\n4 3\n255\n"));
+
+    assert!(
+        render(&["--width=4", "--height=3", "--format=ppm", "-o", path])
+            .status
+            .success()
+    );
+    assert!(fs::read(&output).unwrap().starts_with(b"P3\n4 3\n255\n"));
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    camera::Camera,
+    canvas::{canvas, Canvas},
+    color::{color, Color},
+    tuple::{vector, Tuple},
+    world::World,
+};
+
+// Arbitrary output values: per-pixel data about the first surface the ray
+// through the middle of each pixel hits, rendered alongside the beauty
+// image. Pixels where the ray escapes hold zeros and no object.
+pub struct Aovs {
+    // Distance from the camera to the hit, in every channel.
+    pub depth: Canvas,
+    // World space surface normal (x, y, z), facing the camera.
+    pub normal: Canvas,
+    // The material's color at the hit, before any lighting.
+    pub albedo: Canvas,
+    // Index into `World::objects` of the hit object, row by row.
+    pub objects: Vec<Option<usize>>,
+    // The uuid of each object in the world, by index.
+    pub uuids: Vec<Uuid>,
+}
+
+impl Aovs {
+    pub fn width(&self) -> usize {
+        self.depth.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.depth.height()
+    }
+
+    pub fn object_at(&self, x: usize, y: usize) -> Option<usize> {
+        self.objects[y * self.width() + x]
+    }
+
+    pub fn uuid_at(&self, x: usize, y: usize) -> Option<Uuid> {
+        self.object_at(x, y).map(|i| self.uuids[i])
+    }
+
+    // White where `uuid` was hit, black elsewhere.
+    pub fn mask(&self, uuid: Uuid) -> Canvas {
+        return self.object_image(|i| {
+            if self.uuids[i] == uuid {
+                color(1., 1., 1.)
+            } else {
+                color(0., 0., 0.)
+            }
+        });
+    }
+
+    // Object index plus one in every channel, so the background is 0.
+    pub fn object_index(&self) -> Canvas {
+        return self.object_image(|i| {
+            let v = (i + 1) as f64;
+            color(v, v, v)
+        });
+    }
+
+    // A distinct color for every object, for looking at rather than
+    // compositing.
+    pub fn object_colors(&self) -> Canvas {
+        return self.object_image(|i| {
+            // golden ratio steps spread neighbouring indices around the hue circle
+            let hue = (i as f64 * 0.618034).fract();
+            hue_color(hue)
+        });
+    }
+
+    // Depth scaled so the farthest hit is 1, for viewing.
+    pub fn normalized_depth(&self) -> Canvas {
+        let far = self.depth.pixels.iter().cloned().fold(0., f64::max);
+        let mut image = canvas(self.width(), self.height());
+        if far > 0. {
+            for (out, d) in image.pixels.iter_mut().zip(&self.depth.pixels) {
+                *out = d / far;
+            }
+        }
+        return image;
+    }
+
+    // Normals remapped from -1..1 to 0..1, as in a normal map.
+    pub fn normal_colors(&self) -> Canvas {
+        let mut image = canvas(self.width(), self.height());
+        for (i, out) in image.pixels.iter_mut().enumerate() {
+            if self.objects[i / 3].is_some() {
+                *out = self.normal.pixels[i] * 0.5 + 0.5;
+            }
+        }
+        return image;
+    }
+
+    fn object_image<F: Fn(usize) -> Color>(&self, color_of: F) -> Canvas {
+        let mut image = canvas(self.width(), self.height());
+        for (pixel, object) in image.pixels.chunks_mut(3).zip(&self.objects) {
+            if let Some(i) = object {
+                let c = color_of(*i);
+                pixel.copy_from_slice(&[c.r, c.g, c.b]);
+            }
+        }
+        return image;
+    }
+}
+
+fn hue_color(hue: f64) -> Color {
+    let channel = |offset: f64| {
+        let h = (hue + offset).fract() * 6.;
+        (2. - (h - 3.).abs()).clamp(0., 1.) * 0.8 + 0.2
+    };
+    return color(channel(0.), channel(2. / 3.), channel(1. / 3.));
+}
+
+struct Sample {
+    beauty: Color,
+    depth: f64,
+    normal: Tuple,
+    albedo: Color,
+    object: Option<usize>,
+}
+
+fn sample(
+    camera: &Camera,
+    world: &World,
+    indices: &HashMap<Uuid, usize>,
+    x: usize,
+    y: usize,
+) -> Sample {
+    let (beauty, r, hit) = camera.color_and_hit_for_pixel(world, x as i16, y as i16);
+
+    return match hit {
+        Some(hit) => {
+            let point = r.position(hit.t);
+            let mut normal = hit.object.normal_at(point);
+            if normal.dot(r.direction) > 0. {
+                normal = -normal;
+            }
+            Sample {
+                beauty,
+                depth: hit.t,
+                normal,
+                albedo: hit.object.material.color_at(hit.object, point),
+                object: indices.get(&hit.object.uuid).copied(),
+            }
+        }
+        None => Sample {
+            beauty,
+            depth: 0.,
+            normal: vector(0., 0., 0.),
+            albedo: color(0., 0., 0.),
+            object: None,
+        },
+    };
+}
+
+// Renders the beauty image like `render_parallelized` and fills the AOV
+// buffers in the same pass.
+pub fn render_with_aovs(camera: Camera, world: World) -> (Canvas, Aovs) {
+    let (width, height) = (camera.hsize as usize, camera.vsize as usize);
+    let indices: HashMap<Uuid, usize> = world
+        .objects
+        .iter()
+        .enumerate()
+        .map(|(i, o)| (o.uuid, i))
+        .collect();
+    let samples: Vec<Sample> = (0..width * height)
+        .into_par_iter()
+        .map(|i| sample(&camera, &world, &indices, i % width, i / width))
+        .collect();
+
+    let mut beauty = canvas(width, height);
+    let mut aovs = Aovs {
+        depth: canvas(width, height),
+        normal: canvas(width, height),
+        albedo: canvas(width, height),
+        objects: Vec::with_capacity(width * height),
+        uuids: world.objects.iter().map(|o| o.uuid).collect(),
+    };
+    for (i, s) in samples.iter().enumerate() {
+        let range = i * 3..i * 3 + 3;
+        beauty.pixels[range.clone()].copy_from_slice(&[s.beauty.r, s.beauty.g, s.beauty.b]);
+        aovs.depth.pixels[range.clone()].copy_from_slice(&[s.depth; 3]);
+        aovs.normal.pixels[range.clone()].copy_from_slice(&[s.normal.x, s.normal.y, s.normal.z]);
+        aovs.albedo.pixels[range].copy_from_slice(&[s.albedo.r, s.albedo.g, s.albedo.b]);
+        aovs.objects.push(s.object);
+    }
+    return (beauty, aovs);
+}
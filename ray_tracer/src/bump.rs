@@ -0,0 +1,199 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    json::tagged,
+    matrix::{identity, InverseCache, Matrix},
+    microfacet::orthonormal_basis,
+    noise::{perlin, Perlin},
+    scene::{scene_error, SceneError},
+    texture::{UvMapping, UvPattern},
+    tuple::{vector, Tuple},
+};
+
+// Step used for the finite differences below.
+const DELTA: f64 = 0.0001;
+
+// Tilts a surface normal without changing the geometry. Both the point and
+// the normal are in object space; `transform` places the bump on the object
+// the same way a pattern's transform does.
+pub trait BumpMap: Send + Sync {
+    fn transform(&self) -> &Matrix<f64>;
+
+    fn perturb(&self, object_point: Tuple, normal: Tuple) -> Tuple;
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        Err(scene_error("this bump map cannot be serialized"))
+    }
+
+    // Like `Pattern::problems`.
+    fn problems(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+// Bumps following a fractal noise height field.
+#[derive(Serialize, Deserialize)]
+pub struct NoiseBump {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub noise: Perlin,
+    pub amount: f64,
+    pub scale: f64,
+    pub octaves: u32,
+}
+
+impl BumpMap for NoiseBump {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn problems(&self) -> Vec<String> {
+        if self.scale == 0. || !self.scale.is_finite() {
+            return vec![format!(
+                "scale must be finite and not 0, found {}",
+                self.scale
+            )];
+        }
+        return vec![];
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("noise", self)
+    }
+
+    fn perturb(&self, object_point: Tuple, normal: Tuple) -> Tuple {
+        return tilt_by_height(object_point, normal, |p| {
+            let q = self.inverse.apply(&self.transform, p) / self.scale;
+            self.amount * self.noise.fractal(q, self.octaves)
+        });
+    }
+}
+
+// Concentric ripples spreading out from the y axis, like a drop hitting
+// still water.
+#[derive(Serialize, Deserialize)]
+pub struct RippleBump {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub amplitude: f64,
+    pub wavelength: f64,
+}
+
+impl BumpMap for RippleBump {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("ripples", self)
+    }
+
+    fn perturb(&self, object_point: Tuple, normal: Tuple) -> Tuple {
+        return tilt_by_height(object_point, normal, |p| {
+            let q = self.inverse.apply(&self.transform, p);
+            let distance = (q.x.powi(2) + q.z.powi(2)).sqrt();
+            self.amplitude * (2. * PI * distance / self.wavelength).sin()
+        });
+    }
+}
+
+// A tangent-space normal map: red and green tilt the normal towards +u and
+// +v, blue keeps it pointing along the surface normal, each remapped from
+// 0..1 to -1..1. A flat map is the color (0.5, 0.5, 1).
+#[derive(Serialize, Deserialize)]
+pub struct ImageNormalMap {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub mapping: UvMapping,
+    pub normal_map: Box<dyn UvPattern>,
+}
+
+impl BumpMap for ImageNormalMap {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn problems(&self) -> Vec<String> {
+        self.normal_map.problems()
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("normal-map", self)
+    }
+
+    fn perturb(&self, object_point: Tuple, normal: Tuple) -> Tuple {
+        let to_bump = |p: Tuple| self.inverse.apply(&self.transform, p);
+        let u_at = |p: Tuple| self.mapping.map(to_bump(p)).0;
+
+        let (u, v) = self.mapping.map(to_bump(object_point));
+        let c = self.normal_map.uv_pattern_at(u, v);
+        let (x, y, z) = (c.r * 2. - 1., c.g * 2. - 1., c.b * 2. - 1.);
+
+        // +u runs along the gradient of u within the tangent plane; wrapping
+        // differences keep seams from producing a huge spurious gradient.
+        let (e1, e2) = orthonormal_basis(normal);
+        let du = |e: Tuple| {
+            let d = u_at(object_point + e * DELTA) - u_at(object_point - e * DELTA);
+            (d + 0.5).rem_euclid(1.) - 0.5
+        };
+        let gradient = e1 * du(e1) + e2 * du(e2);
+        let tangent = if gradient.magnitude() > 0. {
+            gradient.normalize()
+        } else {
+            e1
+        };
+        let bitangent = tangent.cross(normal);
+
+        return (tangent * x + bitangent * y + normal * z).normalize();
+    }
+}
+
+// Tilts `normal` against the slope of `height` along the surface, as if the
+// surface were displaced along its normal by that height.
+fn tilt_by_height<F: Fn(Tuple) -> f64>(point: Tuple, normal: Tuple, height: F) -> Tuple {
+    let slope =
+        |axis: Tuple| (height(point + axis * DELTA) - height(point - axis * DELTA)) / (2. * DELTA);
+    let gradient = vector(
+        slope(vector(1., 0., 0.)),
+        slope(vector(0., 1., 0.)),
+        slope(vector(0., 0., 1.)),
+    );
+    let along_surface = gradient - normal * gradient.dot(normal);
+
+    return (normal - along_surface).normalize();
+}
+
+pub fn noise_bump(amount: f64) -> NoiseBump {
+    NoiseBump {
+        transform: identity(),
+        inverse: InverseCache::default(),
+        noise: perlin(0),
+        amount,
+        scale: 1.,
+        octaves: 3,
+    }
+}
+
+pub fn ripple_bump(amplitude: f64, wavelength: f64) -> RippleBump {
+    RippleBump {
+        transform: identity(),
+        inverse: InverseCache::default(),
+        amplitude,
+        wavelength,
+    }
+}
+
+pub fn image_normal_map(mapping: UvMapping, normal_map: Box<dyn UvPattern>) -> ImageNormalMap {
+    ImageNormalMap {
+        transform: identity(),
+        inverse: InverseCache::default(),
+        mapping,
+        normal_map,
+    }
+}
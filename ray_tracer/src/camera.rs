@@ -1,14 +1,17 @@
 use crate::{
     canvas::*,
+    color::{black, Color},
     matrix::{identity, Matrix},
-    ray::{ray, Ray},
+    ray::{ray, Intersection, Ray},
     transforms::translation,
     tuple::{point, Tuple},
-    utils::RECURSION_DEPTH,
+    utils::{mix_seed, RECURSION_DEPTH},
     world::World,
 };
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct Camera {
     pub hsize: i16,
     pub vsize: i16,
@@ -17,6 +20,13 @@ pub struct Camera {
     pub half_height: f64,
     pub pixel_size: f64,
     pub transform: Matrix<f64>,
+    // Rays averaged per pixel; more than one smooths jagged edges.
+    #[serde(default = "one_sample")]
+    pub samples: usize,
+}
+
+fn one_sample() -> usize {
+    1
 }
 
 impl Camera {
@@ -32,12 +42,19 @@ impl Camera {
             half_width,
             half_height,
             transform: identity(),
+            samples: 1,
         }
     }
 
     pub fn ray_for_pixel(&self, x: i16, y: i16) -> Ray {
-        let x_offset = (x as f64 + 0.5) * self.pixel_size;
-        let y_offset = (y as f64 + 0.5) * self.pixel_size;
+        self.ray_through(x, y, 0.5, 0.5)
+    }
+
+    // The ray through the point (dx, dy) of pixel (x, y), where both offsets
+    // run from 0 to 1 across the pixel starting at its top left corner.
+    pub fn ray_through(&self, x: i16, y: i16, dx: f64, dy: f64) -> Ray {
+        let x_offset = (x as f64 + dx) * self.pixel_size;
+        let y_offset = (y as f64 + dy) * self.pixel_size;
 
         let world_x = self.half_width - x_offset;
         let world_y = self.half_height - y_offset;
@@ -51,6 +68,45 @@ impl Camera {
         return ray(origin, direction);
     }
 
+    // Any random sampling is seeded from the pixel and sample, so the same
+    // camera and world always render the same image.
+    pub fn color_for_pixel(&self, world: &World, x: i16, y: i16) -> Color {
+        let pixel_seed = mix_seed(x as u64, y as u64);
+        if self.samples <= 1 {
+            let r = self.ray_for_pixel(x, y);
+            return world.color_at_seeded(&r, RECURSION_DEPTH, mix_seed(pixel_seed, 0));
+        }
+
+        let mut sum = black();
+        for i in 0..self.samples {
+            let (dx, dy) = sample_offset(i, self.samples);
+            let r = self.ray_through(x, y, dx, dy);
+            let seed = mix_seed(pixel_seed, i as u64);
+            sum = sum + world.color_at_seeded(&r, RECURSION_DEPTH, seed);
+        }
+        return sum * (1. / self.samples as f64);
+    }
+
+    // `color_for_pixel` plus the ray through the middle of the pixel and
+    // what it hits. Without supersampling that is the ray the color came
+    // from, so it is only traced once.
+    pub fn color_and_hit_for_pixel<'a>(
+        &self,
+        world: &'a World,
+        x: i16,
+        y: i16,
+    ) -> (Color, Ray, Option<Intersection<'a>>) {
+        let r = self.ray_for_pixel(x, y);
+        if self.samples <= 1 {
+            let seed = mix_seed(mix_seed(x as u64, y as u64), 0);
+            let (c, hit) = world.color_and_hit_seeded(&r, RECURSION_DEPTH, seed);
+            return (c, r, hit);
+        }
+
+        let hit = world.intersect(&r).hit();
+        return (self.color_for_pixel(world, x, y), r, hit);
+    }
+
     fn compute_fields(hsize: i16, vsize: i16, field_of_view: f64) -> (f64, f64, f64) {
         let half_view = (field_of_view / 2.).tan();
         let aspect = (hsize as f64) / (vsize as f64);
@@ -69,6 +125,22 @@ impl Camera {
     }
 }
 
+// The i-th of n points of a Hammersley set, which spreads any number of
+// samples evenly over the pixel without the banding of a regular grid.
+fn sample_offset(i: usize, n: usize) -> (f64, f64) {
+    let mut bits = i as u32;
+    let mut inverse = 0.;
+    let mut place = 0.5;
+    while bits > 0 {
+        if bits & 1 == 1 {
+            inverse += place;
+        }
+        bits >>= 1;
+        place /= 2.;
+    }
+    return ((i as f64 + 0.5) / n as f64, inverse + 0.5 / n as f64);
+}
+
 pub fn view_transform(from: Tuple, to: Tuple, up: Tuple) -> Matrix<f64> {
     let forward = (to - from).normalize();
     let upn = up.normalize();
@@ -93,9 +165,7 @@ pub fn render(camera: Camera, world: World) -> Canvas {
     let mut image = canvas(camera.hsize as usize, camera.vsize as usize);
     for y in 0..camera.vsize {
         for x in 0..camera.hsize {
-            let ray = &camera.ray_for_pixel(x, y);
-
-            let color = world.color_at(&ray, RECURSION_DEPTH);
+            let color = camera.color_for_pixel(&world, x, y);
 
             match image.write_pixel(x as usize, y as usize, color) {
                 Err(e) => println!("error rendering to pixel: {:?}", e),
@@ -114,8 +184,7 @@ pub fn render_parallelized(camera: Camera, world: World) -> Canvas {
     bands.into_par_iter().for_each(|(i, band)| {
         // for y in 0..1 {
         for x in 0..width {
-            let ray = &camera.ray_for_pixel(x as i16, i as i16);
-            let color = world.color_at(&ray, RECURSION_DEPTH);
+            let color = camera.color_for_pixel(&world, x as i16, i as i16);
 
             let index = (3 * x) as usize;
             band[index] = color.r;
@@ -1,18 +1,85 @@
 use crate::color::*;
+use crate::tonemap::ToneMap;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Dimensions {
     pub width: usize,
     pub height: usize,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Canvas {
     pub dimensions: Dimensions,
     pub pixels: Vec<f64>,
 }
 
+// `PNG` is 8 bits per channel, `PNG16` is 16. `PPM` is the ASCII P3 form
+// and `PPMBinary` the much smaller P6. `PFM`, `HDR` and `EXR` keep values
+// outside 0..1 instead of clamping them.
+//
+// PNG and P6 store sRGB encoded values and are decoded again when loaded.
+// P3 stays linear, as in the book, so its output can be checked by eye.
+// It has to be asked for by type: a `.ppm` path means P6.
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum ImageType {
     PNG,
+    PNG16,
     PPM,
+    PPMBinary,
+    PFM,
+    HDR,
+    EXR,
+}
+
+// How values are stored in the integer formats. Colors are sRGB encoded,
+// but data such as depths or normals should keep their values as they are.
+// PNGs written linearly are tagged with a gamma of 1 so `load` can tell.
+// P3 is always linear and the float formats ignore this.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Transfer {
+    Srgb,
+    Linear,
+}
+
+impl ImageType {
+    // Picks the format from the file extension, ignoring case.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<ImageType> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        return match extension.as_deref() {
+            Some("ppm") => Ok(ImageType::PPMBinary),
+            Some("png") => Ok(ImageType::PNG),
+            Some("pfm") => Ok(ImageType::PFM),
+            Some("hdr") => Ok(ImageType::HDR),
+            Some("exr") => Ok(ImageType::EXR),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported image format: {}", path.display()),
+            )),
+        };
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageType::PNG | ImageType::PNG16 => "png",
+            ImageType::PPM | ImageType::PPMBinary => "ppm",
+            ImageType::PFM => "pfm",
+            ImageType::HDR => "hdr",
+            ImageType::EXR => "exr",
+        }
+    }
+
+    // Whether values outside 0..1 survive being written.
+    pub fn is_float(&self) -> bool {
+        matches!(self, ImageType::PFM | ImageType::HDR | ImageType::EXR)
+    }
 }
 
 pub fn canvas(width: usize, height: usize) -> Canvas {
@@ -74,80 +141,536 @@ impl Canvas {
         self.dimensions.width
     }
 
-    pub fn save(&self, image_type: ImageType, filename: &str) {
-        match image_type {
-            ImageType::PPM => render::save_as_ppm(&self, filename),
-            _ => panic!("ImageType not yet supported"),
+    // Writes the image to `path` in the format its extension names,
+    // creating any missing parent directories.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let image_type = ImageType::from_path(&path)?;
+        return self.save_as(path, image_type);
+    }
+
+    pub fn save_as<P: AsRef<Path>>(&self, path: P, image_type: ImageType) -> io::Result<()> {
+        return self.save_with(path, image_type, Transfer::Srgb);
+    }
+
+    pub fn save_with<P: AsRef<Path>>(
+        &self,
+        path: P,
+        image_type: ImageType,
+        transfer: Transfer,
+    ) -> io::Result<()> {
+        return render::save(self, path.as_ref(), image_type, transfer);
+    }
+
+    // Runs every pixel through `operators` in order.
+    pub fn tone_map(&mut self, operators: &[ToneMap]) {
+        for pixel in self.pixels.chunks_mut(3) {
+            let mut c = color(pixel[0], pixel[1], pixel[2]);
+            for operator in operators {
+                c = operator.map(c);
+            }
+            pixel.copy_from_slice(&[c.r, c.g, c.b]);
         }
     }
 
     pub fn canvas_to_ppm(&self) -> String {
-        return render::canvas_to_ppm(&self);
+        return render::canvas_to_ppm(self);
+    }
+
+    pub fn canvas_to_ppm_binary(&self) -> Vec<u8> {
+        return render::canvas_to_ppm_binary(self);
+    }
+
+    pub fn write<W: io::Write>(&self, out: W, image_type: ImageType) -> io::Result<()> {
+        return self.write_with(out, image_type, Transfer::Srgb);
+    }
+
+    pub fn write_with<W: io::Write>(
+        &self,
+        out: W,
+        image_type: ImageType,
+        transfer: Transfer,
+    ) -> io::Result<()> {
+        return render::write(self, out, image_type, transfer);
+    }
+
+    pub fn canvas_to_png(&self, bit_depth: png::BitDepth) -> io::Result<Vec<u8>> {
+        return render::canvas_to_png(self, bit_depth);
+    }
+
+    // Reads a PPM (P3 or P6) or PNG image, picking the decoder from the file
+    // extension. Channel values are scaled into 0..1 and sRGB encoded ones
+    // decoded, so loading what `save` wrote gives back the linear values.
+    // PNGs tagged with a gamma of 1 are read as they are. P6 samples are
+    // taken as sRGB and P3 ones as linear, matching what the writers store,
+    // so the same numbers load as different colors in the two PPM forms.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Canvas> {
+        return read::load(path.as_ref());
     }
 }
 
 pub mod render {
-    use super::{Canvas, Dimensions};
-    use std::fs::File;
-    use std::io::{self, Write};
+    use super::{Canvas, Dimensions, ImageType, Transfer};
+    use crate::tonemap::srgb_encode;
+    use std::fs::{self, File};
+    use std::io::{self, BufWriter, Error, ErrorKind, Write};
     use std::path::Path;
 
-    pub fn save_as(filepath: &String, data: String) {
-        match write_file(filepath, data) {
-            Ok(()) => println!("Writing to file was successful"),
-            Err(ex) => println!("Something went wrong: {}", ex),
+    pub fn save(
+        canvas: &Canvas,
+        path: &Path,
+        image_type: ImageType,
+        transfer: Transfer,
+    ) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
         }
+        let mut file = BufWriter::new(File::create(path)?);
+        write(canvas, &mut file, image_type, transfer)?;
+        return file.flush();
     }
 
-    pub fn save_as_ppm(canvas: &Canvas, filename: &str) {
-        let data = canvas_to_ppm(canvas);
-        let path = format!("./images/{}.ppm", filename);
-        save_as(&path, data);
+    // Streams the encoded image to `out` without building it in memory first.
+    pub fn write<W: Write>(
+        canvas: &Canvas,
+        out: W,
+        image_type: ImageType,
+        transfer: Transfer,
+    ) -> io::Result<()> {
+        return match image_type {
+            ImageType::PPM => write_ppm(canvas, out),
+            ImageType::PPMBinary => write_ppm_binary(canvas, out, transfer),
+            ImageType::PNG => write_png(canvas, out, png::BitDepth::Eight, transfer),
+            ImageType::PNG16 => write_png(canvas, out, png::BitDepth::Sixteen, transfer),
+            ImageType::PFM => write_pfm(canvas, out),
+            ImageType::HDR => write_hdr(canvas, out),
+            ImageType::EXR => write_exr(canvas, out),
+        };
     }
 
     pub fn canvas_to_ppm(canvas: &Canvas) -> String {
-        let values: Vec<String> = stringify(&canvas.pixels);
-        return build_ppm(canvas, values);
+        let mut bytes = vec![];
+        write_ppm(canvas, &mut bytes).unwrap();
+        return String::from_utf8(bytes).unwrap();
     }
 
-    fn build_ppm(canvas: &Canvas, values: Vec<String>) -> String {
-        let mut image_data: Vec<String> = vec![];
-        image_data.push(ppm_header(&canvas)); // header
-        let mut result = chunk(values, &mut image_data); //body
-        result.push_str("\n"); // required for ending ppm files
+    pub fn canvas_to_ppm_binary(canvas: &Canvas) -> Vec<u8> {
+        let mut bytes = vec![];
+        write_ppm_binary(canvas, &mut bytes, Transfer::Srgb).unwrap();
+        return bytes;
+    }
 
-        return result;
+    pub fn canvas_to_png(canvas: &Canvas, bit_depth: png::BitDepth) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![];
+        write_png(canvas, &mut bytes, bit_depth, Transfer::Srgb)?;
+        return Ok(bytes);
     }
 
-    fn stringify(values: &Vec<f64>) -> Vec<String> {
-        values.iter().map(|n| rgb_to_u8(n).to_string()).collect()
+    // ASCII P3, 15 values to a line to stay under the 70 character limit.
+    fn write_ppm<W: Write>(canvas: &Canvas, mut out: W) -> io::Result<()> {
+        let Dimensions { width, height } = canvas.dimensions;
+        write!(out, "P3\n{} {}\n255\n", width, height)?;
+        for line in canvas.pixels.chunks(15) {
+            for (i, c) in line.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b" ")?;
+                }
+                write!(out, "{}", linear_to_u8(c))?;
+            }
+            out.write_all(b"\n")?;
+        }
+        return Ok(());
     }
 
-    fn ppm_header(canvas: &Canvas) -> String {
+    // Binary P6: the same header followed by one byte per channel.
+    fn write_ppm_binary<W: Write>(
+        canvas: &Canvas,
+        mut out: W,
+        transfer: Transfer,
+    ) -> io::Result<()> {
         let Dimensions { width, height } = canvas.dimensions;
-        [
-            "P3".to_string(),
-            format!("{} {}", width, height),
-            "255".to_string(),
-        ]
-        .join("\n")
+        write!(out, "P6\n{} {}\n255\n", width, height)?;
+        for row in canvas.pixels.chunks(width.max(1) * 3) {
+            let bytes: Vec<u8> = row.iter().map(|c| to_u8(*c, transfer)).collect();
+            out.write_all(&bytes)?;
+        }
+        return Ok(());
     }
 
-    fn chunk(source: Vec<String>, output: &mut Vec<String>) -> String {
-        for chunk in source.chunks(15) {
-            output.push(chunk.join(" "));
+    // RGB without alpha; 16-bit samples are big-endian as PNG requires. Rows
+    // are encoded as they are converted rather than all at once.
+    fn write_png<W: Write>(
+        canvas: &Canvas,
+        out: W,
+        bit_depth: png::BitDepth,
+        transfer: Transfer,
+    ) -> io::Result<()> {
+        let row_bytes = |row: &[f64]| -> Vec<u8> {
+            match bit_depth {
+                png::BitDepth::Eight => row.iter().map(|c| to_u8(*c, transfer)).collect(),
+                _ => row
+                    .iter()
+                    .flat_map(|c| to_u16(*c, transfer).to_be_bytes())
+                    .collect(),
+            }
+        };
+        if bit_depth != png::BitDepth::Eight && bit_depth != png::BitDepth::Sixteen {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "PNG output must be 8 or 16 bits per channel",
+            ));
+        }
+
+        let Dimensions { width, height } = canvas.dimensions;
+        let mut encoder = png::Encoder::new(out, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(bit_depth);
+        match transfer {
+            Transfer::Srgb => encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual),
+            Transfer::Linear => encoder.set_source_gamma(png::ScaledFloat::new(1.)),
         }
-        return output.join("\n");
+        let mut writer = encoder.write_header().map_err(to_io_error)?;
+        let mut stream = writer.stream_writer().map_err(to_io_error)?;
+        for row in canvas.pixels.chunks(width.max(1) * 3) {
+            stream.write_all(&row_bytes(row))?;
+        }
+        stream.finish().map_err(to_io_error)?;
+        return writer.finish().map_err(to_io_error);
     }
 
-    fn write_file(name: &String, data: String) -> io::Result<()> {
-        let path = Path::new(name);
-        let mut file = File::create(path)?;
-        writeln!(file, "{}", data)?;
-        Ok(())
+    // Portable float map: little-endian f32 RGB, rows from the bottom up.
+    // The negative scale in the header marks the data as little-endian.
+    fn write_pfm<W: Write>(canvas: &Canvas, mut out: W) -> io::Result<()> {
+        let Dimensions { width, height } = canvas.dimensions;
+        write!(out, "PF\n{} {}\n-1.0\n", width, height)?;
+        for row in canvas.pixels.chunks(width.max(1) * 3).rev() {
+            let bytes: Vec<u8> = row.iter().flat_map(|c| (*c as f32).to_le_bytes()).collect();
+            out.write_all(&bytes)?;
+        }
+        return Ok(());
+    }
+
+    // Radiance RGBE with flat (not run-length encoded) scanlines.
+    fn write_hdr<W: Write>(canvas: &Canvas, mut out: W) -> io::Result<()> {
+        let Dimensions { width, height } = canvas.dimensions;
+        write!(
+            out,
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            height, width
+        )?;
+        for row in canvas.pixels.chunks(width.max(1) * 3) {
+            let bytes: Vec<u8> = row
+                .chunks(3)
+                .flat_map(|rgb| to_rgbe(rgb[0], rgb[1], rgb[2]))
+                .collect();
+            out.write_all(&bytes)?;
+        }
+        return Ok(());
+    }
+
+    // A shared exponent plus 8-bit mantissas. Negative values become 0.
+    pub fn to_rgbe(r: f64, g: f64, b: f64) -> [u8; 4] {
+        let (r, g, b) = (r.max(0.), g.max(0.), b.max(0.));
+        let v = r.max(g).max(b);
+        if v < 1e-32 {
+            return [0, 0, 0, 0];
+        }
+        if !v.is_finite() {
+            return [255, 255, 255, 255];
+        }
+
+        // v = mantissa * 2^exponent with mantissa in 0.5..1
+        let mut exponent = v.log2().floor() as i32 + 1;
+        if v / 2f64.powi(exponent) >= 1. {
+            exponent += 1;
+        }
+        let scale = 256. / 2f64.powi(exponent);
+        let channel = |c: f64| (c * scale).clamp(0., 255.) as u8;
+        return [
+            channel(r),
+            channel(g),
+            channel(b),
+            (exponent + 128).clamp(0, 255) as u8,
+        ];
+    }
+
+    // Single part, scanline OpenEXR with uncompressed 32-bit float channels.
+    fn write_exr<W: Write>(canvas: &Canvas, mut out: W) -> io::Result<()> {
+        let Dimensions { width, height } = canvas.dimensions;
+        // the data window is inclusive, so it cannot describe an empty image
+        if width == 0 || height == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("cannot write an empty {}x{} image as EXR", width, height),
+            ));
+        }
+        let (max_x, max_y) = (width as i32 - 1, height as i32 - 1);
+        let window: Vec<u8> = [0, 0, max_x, max_y]
+            .iter()
+            .flat_map(|v: &i32| v.to_le_bytes())
+            .collect();
+
+        let mut channels = vec![];
+        // channels are listed, and stored, in alphabetical order
+        for name in ["B", "G", "R"] {
+            channels.extend_from_slice(name.as_bytes());
+            channels.push(0);
+            channels.extend_from_slice(&2i32.to_le_bytes()); // FLOAT
+            channels.extend_from_slice(&[0, 0, 0, 0]); // pLinear, reserved
+            channels.extend_from_slice(&1i32.to_le_bytes()); // x sampling
+            channels.extend_from_slice(&1i32.to_le_bytes()); // y sampling
+        }
+        channels.push(0);
+
+        let mut header = vec![];
+        header.extend_from_slice(&20000630u32.to_le_bytes());
+        header.extend_from_slice(&2u32.to_le_bytes());
+        let mut attribute = |name: &str, kind: &str, value: &[u8]| {
+            header.extend_from_slice(name.as_bytes());
+            header.push(0);
+            header.extend_from_slice(kind.as_bytes());
+            header.push(0);
+            header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+            header.extend_from_slice(value);
+        };
+        attribute("channels", "chlist", &channels);
+        attribute("compression", "compression", &[0]);
+        attribute("dataWindow", "box2i", &window);
+        attribute("displayWindow", "box2i", &window);
+        attribute("lineOrder", "lineOrder", &[0]);
+        attribute("pixelAspectRatio", "float", &1f32.to_le_bytes());
+        attribute("screenWindowCenter", "v2f", &[0; 8]);
+        attribute("screenWindowWidth", "float", &1f32.to_le_bytes());
+        header.push(0);
+        out.write_all(&header)?;
+
+        // one scanline per block, each block found through the offset table
+        let block_size = 8 + width * 3 * 4;
+        let first_block = header.len() + height * 8;
+        for y in 0..height {
+            let offset = (first_block + y * block_size) as u64;
+            out.write_all(&offset.to_le_bytes())?;
+        }
+        for (y, row) in canvas.pixels.chunks(width.max(1) * 3).enumerate() {
+            let mut block = Vec::with_capacity(block_size);
+            block.extend_from_slice(&(y as i32).to_le_bytes());
+            block.extend_from_slice(&((width * 3 * 4) as i32).to_le_bytes());
+            for channel in [2, 1, 0] {
+                for x in 0..width {
+                    block.extend_from_slice(&(row[x * 3 + channel] as f32).to_le_bytes());
+                }
+            }
+            out.write_all(&block)?;
+        }
+        return Ok(());
     }
 
-    fn rgb_to_u8(c: &f64) -> u8 {
+    fn to_io_error(e: png::EncodingError) -> Error {
+        match e {
+            png::EncodingError::IoError(e) => e,
+            e => Error::new(ErrorKind::InvalidInput, e.to_string()),
+        }
+    }
+
+    fn linear_to_u8(c: &f64) -> u8 {
         (255. * c).round().clamp(0., 255.) as u8
     }
+
+    fn to_u8(c: f64, transfer: Transfer) -> u8 {
+        match transfer {
+            Transfer::Srgb => (255. * srgb_encode(c)).round() as u8,
+            Transfer::Linear => linear_to_u8(&c),
+        }
+    }
+
+    fn to_u16(c: f64, transfer: Transfer) -> u16 {
+        match transfer {
+            Transfer::Srgb => (65535. * srgb_encode(c)).round() as u16,
+            Transfer::Linear => (65535. * c).round().clamp(0., 65535.) as u16,
+        }
+    }
+}
+
+pub mod read {
+    use super::{canvas, Canvas, ImageType};
+    use crate::tonemap::srgb_decode;
+    use std::fs;
+    use std::io::{self, Error, ErrorKind};
+    use std::path::Path;
+
+    pub fn load(path: &Path) -> io::Result<Canvas> {
+        let image_type = ImageType::from_path(path)?;
+        let data = fs::read(path)?;
+
+        return match image_type {
+            ImageType::PPM | ImageType::PPMBinary => canvas_from_ppm(&data),
+            ImageType::PNG | ImageType::PNG16 => canvas_from_png(&data),
+            ImageType::PFM | ImageType::HDR | ImageType::EXR => Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("cannot read HDR images: {}", path.display()),
+            )),
+        };
+    }
+
+    pub fn canvas_from_ppm(data: &[u8]) -> io::Result<Canvas> {
+        let mut tokens = PpmTokens { data, position: 0 };
+
+        let magic = tokens.next_token()?;
+        let width = tokens.next_number()?;
+        let height = tokens.next_number()?;
+        let maxval = tokens.next_number()?;
+        if maxval == 0 || maxval > 65535 {
+            return Err(invalid_data(format!("invalid PPM maxval {}", maxval)));
+        }
+
+        let samples = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(3))
+            .ok_or_else(|| invalid_data(format!("PPM is too large: {}x{}", width, height)))?;
+        let scale = maxval as f64;
+
+        // The header's size is checked against the data before allocating,
+        // so a corrupt one can't ask for more memory than the file justifies.
+        match magic.as_str() {
+            "P3" => {
+                // every sample takes at least one byte of the file
+                if samples > data.len() {
+                    return Err(invalid_data("PPM raster is truncated".to_string()));
+                }
+
+                let mut image = canvas(width, height);
+                for i in 0..samples {
+                    let value = tokens.next_number()?;
+                    if value > maxval {
+                        return Err(above_maxval(value, maxval));
+                    }
+                    image.pixels[i] = value as f64 / scale;
+                }
+                return Ok(image);
+            }
+            "P6" => {
+                // a single whitespace byte separates the header from the raster
+                let start = tokens.position + 1;
+                let bytes_per_sample = if maxval < 256 { 1 } else { 2 };
+                let raster = samples
+                    .checked_mul(bytes_per_sample)
+                    .and_then(|length| data.get(start..start.checked_add(length)?))
+                    .ok_or_else(|| invalid_data("PPM raster is truncated".to_string()))?;
+
+                let mut image = canvas(width, height);
+                for i in 0..samples {
+                    let value = if bytes_per_sample == 1 {
+                        raster[i] as usize
+                    } else {
+                        (raster[2 * i] as usize) << 8 | raster[2 * i + 1] as usize
+                    };
+                    if value > maxval {
+                        return Err(above_maxval(value, maxval));
+                    }
+                    image.pixels[i] = srgb_decode(value as f64 / scale);
+                }
+                return Ok(image);
+            }
+            _ => return Err(invalid_data(format!("unsupported PPM type {}", magic))),
+        }
+    }
+
+    pub fn canvas_from_png(data: &[u8]) -> io::Result<Canvas> {
+        let mut decoder = png::Decoder::new(data);
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder
+            .read_info()
+            .map_err(|e| invalid_data(e.to_string()))?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buffer)
+            .map_err(|e| invalid_data(e.to_string()))?;
+        let bytes = &buffer[..info.buffer_size()];
+        let png_info = reader.info();
+        let linear =
+            png_info.srgb.is_none() && png_info.source_gamma == Some(png::ScaledFloat::new(1.));
+
+        let channels = info.color_type.samples();
+        let (max, bytes_per_sample) = match info.bit_depth {
+            png::BitDepth::Sixteen => (65535., 2),
+            _ => (255., 1),
+        };
+        let sample = |i: usize| -> f64 {
+            let value = if bytes_per_sample == 1 {
+                bytes[i] as f64
+            } else {
+                ((bytes[2 * i] as usize) << 8 | bytes[2 * i + 1] as usize) as f64
+            };
+            if linear {
+                value / max
+            } else {
+                srgb_decode(value / max)
+            }
+        };
+
+        let (width, height) = (info.width as usize, info.height as usize);
+        let mut image = canvas(width, height);
+        for p in 0..width * height {
+            let base = p * channels;
+            let rgb = if channels < 3 {
+                let grey = sample(base);
+                [grey, grey, grey]
+            } else {
+                [sample(base), sample(base + 1), sample(base + 2)]
+            };
+            image.pixels[p * 3..p * 3 + 3].copy_from_slice(&rgb);
+        }
+
+        return Ok(image);
+    }
+
+    struct PpmTokens<'a> {
+        data: &'a [u8],
+        position: usize,
+    }
+
+    impl<'a> PpmTokens<'a> {
+        // Next whitespace separated token, skipping `#` comments.
+        fn next_token(&mut self) -> io::Result<String> {
+            loop {
+                match self.data.get(self.position) {
+                    Some(b'#') => {
+                        while let Some(c) = self.data.get(self.position) {
+                            if *c == b'\n' {
+                                break;
+                            }
+                            self.position += 1;
+                        }
+                    }
+                    Some(c) if c.is_ascii_whitespace() => self.position += 1,
+                    Some(_) => break,
+                    None => return Err(invalid_data("unexpected end of PPM data".to_string())),
+                }
+            }
+
+            let start = self.position;
+            while let Some(c) = self.data.get(self.position) {
+                if c.is_ascii_whitespace() || *c == b'#' {
+                    break;
+                }
+                self.position += 1;
+            }
+
+            return Ok(String::from_utf8_lossy(&self.data[start..self.position]).to_string());
+        }
+
+        fn next_number(&mut self) -> io::Result<usize> {
+            let token = self.next_token()?;
+            return token
+                .parse()
+                .map_err(|_| invalid_data(format!("expected a number in PPM, found {:?}", token)));
+        }
+    }
+
+    fn invalid_data(message: String) -> Error {
+        Error::new(ErrorKind::InvalidData, message)
+    }
+
+    fn above_maxval(value: usize, maxval: usize) -> Error {
+        invalid_data(format!("PPM sample {} is above maxval {}", value, maxval))
+    }
 }
@@ -1,15 +1,32 @@
 use crate::utils::*;
+use serde::{Deserialize, Serialize};
 use std::ops;
 
-#[derive(Debug, Copy, Clone)]
-
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct Color {
     pub r: Scalar,
     pub b: Scalar,
     pub g: Scalar,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+}
+
+pub const CHANNELS: [Channel; 3] = [Channel::Red, Channel::Green, Channel::Blue];
+
 impl Color {
+    pub fn channel(&self, channel: Channel) -> Scalar {
+        match channel {
+            Channel::Red => self.r,
+            Channel::Green => self.g,
+            Channel::Blue => self.b,
+        }
+    }
+
     fn add_color(&self, b: Color) -> Color {
         Color {
             r: self.r + b.r,
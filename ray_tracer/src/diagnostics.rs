@@ -0,0 +1,264 @@
+use std::fmt;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    material::{Dispersion, Material},
+    matrix::Matrix,
+    pattern::Pattern,
+    ray::ray,
+    shapes::object::{Object, Shape},
+    tuple::{vector, Tuple},
+    world::World,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+// A problem found by `World::validate`. Errors would panic or produce
+// garbage mid-render; warnings are probably mistakes but render fine.
+// `subject` names the offending part of the world, e.g. "object 2".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub subject: String,
+    pub uuid: Option<Uuid>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        match self.uuid {
+            Some(uuid) => write!(
+                f,
+                "{}: {} ({}): {}",
+                severity, self.subject, uuid, self.message
+            ),
+            None => write!(f, "{}: {}: {}", severity, self.subject, self.message),
+        }
+    }
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.is_error())
+}
+
+// Collects diagnostics about one subject.
+struct Report<'a> {
+    diagnostics: &'a mut Vec<Diagnostic>,
+    subject: String,
+    uuid: Option<Uuid>,
+}
+
+impl Report<'_> {
+    fn add(&mut self, severity: Severity, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            subject: self.subject.clone(),
+            uuid: self.uuid,
+            message,
+        });
+    }
+
+    fn error(&mut self, message: String) {
+        self.add(Severity::Error, message);
+    }
+
+    fn warning(&mut self, message: String) {
+        self.add(Severity::Warning, message);
+    }
+
+    // Returns whether the transform is usable, i.e. can be inverted.
+    fn transform(&mut self, name: &str, transform: &Matrix<f64>) -> bool {
+        if transform.dimensions() != (4, 4) {
+            let (cols, rows) = transform.dimensions();
+            self.error(format!("{} is {}x{}, not 4x4", name, rows, cols));
+            return false;
+        }
+        if transform.data.iter().flatten().any(|v| !v.is_finite()) {
+            self.error(format!("{} contains NaN or infinite values", name));
+            return false;
+        }
+        if transform.inverse().is_none() {
+            self.error(format!(
+                "{} cannot be inverted (is something scaled by 0?)",
+                name
+            ));
+            return false;
+        }
+        return true;
+    }
+
+    fn fraction(&mut self, name: &str, value: f64) {
+        if !(0. ..=1.).contains(&value) {
+            self.warning(format!("{} is {}, expected 0 to 1", name, value));
+        }
+    }
+
+    fn material(&mut self, material: &Material) {
+        let n = material.refractive_index;
+        if !n.is_finite() || n <= 0. {
+            self.error(format!("refractive_index must be positive, found {}", n));
+        }
+        match material.dispersion {
+            Dispersion::Abbe(v) if !v.is_finite() || v <= 0. => {
+                self.error(format!("Abbe number must be positive, found {}", v))
+            }
+            Dispersion::PerChannel(r, g, b) if [r, g, b].iter().any(|n| *n <= 0.) => {
+                self.error(format!(
+                    "per-channel refractive indices must be positive, found ({}, {}, {})",
+                    r, g, b
+                ))
+            }
+            _ => {}
+        }
+
+        self.fraction("reflective", material.reflective);
+        self.fraction("transparency", material.transparency);
+        self.fraction("roughness", material.roughness);
+
+        let patterns: [(&str, &Option<Arc<dyn Pattern>>); 5] = [
+            ("pattern", &material.pattern),
+            ("reflective pattern", &material.reflective_pattern),
+            ("specular pattern", &material.specular_pattern),
+            ("transparency pattern", &material.transparency_pattern),
+            ("roughness pattern", &material.roughness_pattern),
+        ];
+        for (name, pattern) in patterns {
+            if let Some(pattern) = pattern {
+                self.pattern(name, pattern.as_ref());
+            }
+        }
+        if let Some(bump) = &material.bump {
+            self.transform("bump transform", bump.transform());
+            for problem in bump.problems() {
+                self.error(format!("bump {}", problem));
+            }
+        }
+    }
+
+    // Nested patterns are named by the slots leading to them, e.g.
+    // "pattern.a.b".
+    fn pattern(&mut self, name: &str, pattern: &dyn Pattern) {
+        self.transform(&format!("{} transform", name), pattern.transform());
+        for problem in pattern.problems() {
+            self.error(format!("{} {}", name, problem));
+        }
+        for (slot, child) in pattern.children() {
+            self.pattern(&format!("{}.{}", name, slot), child);
+        }
+    }
+}
+
+// Whether `p` lies inside a closed object. Only meaningful for objects with
+// an invertible transform.
+fn contains(object: &Object, p: Tuple) -> bool {
+    return match object.shape {
+        Shape::Plane(_) => false,
+        Shape::Sphere(_) => {
+            let xs = object.intersect(&ray(p, vector(0., 1., 0.)));
+            xs.locations.iter().any(|i| i.t < 0.) && xs.locations.iter().any(|i| i.t > 0.)
+        }
+    };
+}
+
+fn is_opaque(material: &Material) -> bool {
+    material.transparency == 0. && material.transparency_pattern.is_none()
+}
+
+pub fn validate_world(world: &World) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+
+    let mut report = Report {
+        diagnostics: &mut diagnostics,
+        subject: "light".to_string(),
+        uuid: None,
+    };
+    let intensity = world.light.intensity;
+    if [intensity.r, intensity.g, intensity.b]
+        .iter()
+        .any(|c| !c.is_finite() || *c < 0.)
+    {
+        report.error("intensity must be finite and not negative".to_string());
+    } else if intensity.r + intensity.g + intensity.b == 0. {
+        report.warning("intensity is black, the scene will be unlit".to_string());
+    }
+
+    if world.objects.is_empty() {
+        let mut report = Report {
+            diagnostics: &mut diagnostics,
+            subject: "world".to_string(),
+            uuid: None,
+        };
+        report.warning("there are no objects to render".to_string());
+    }
+
+    for (index, object) in world.objects.iter().enumerate() {
+        let mut report = Report {
+            diagnostics: &mut diagnostics,
+            subject: format!("object {}", index),
+            uuid: Some(object.uuid),
+        };
+        let invertible = report.transform("transform", &object.transform);
+        report.material(&object.material);
+
+        if invertible && is_opaque(&object.material) && contains(object, world.light.position) {
+            report.warning("the light is inside this opaque object".to_string());
+        }
+        if let Some(first) = world.objects[..index]
+            .iter()
+            .position(|o| o.uuid == object.uuid)
+        {
+            // refraction tracks which objects a ray is inside by uuid
+            report.error(format!("has the same uuid as object {}", first));
+        }
+    }
+
+    if let Some(fog) = &world.fog {
+        let mut report = Report {
+            diagnostics: &mut diagnostics,
+            subject: "fog".to_string(),
+            uuid: None,
+        };
+        if !fog.density.is_finite() || fog.density < 0. {
+            report.error(format!(
+                "density must not be negative, found {}",
+                fog.density
+            ));
+        }
+    }
+
+    for (index, medium) in world.media.iter().enumerate() {
+        let mut report = Report {
+            diagnostics: &mut diagnostics,
+            subject: format!("medium {}", index),
+            uuid: Some(medium.boundary.uuid),
+        };
+        report.transform("boundary transform", &medium.boundary.transform);
+        if !medium.step_size.is_finite() || medium.step_size <= 0. {
+            report.error(format!(
+                "step_size must be positive, found {}",
+                medium.step_size
+            ));
+        }
+        if medium.absorption < 0. || medium.scattering < 0. {
+            report.error("absorption and scattering must not be negative".to_string());
+        }
+    }
+
+    return diagnostics;
+}
@@ -0,0 +1,342 @@
+use std::path::PathBuf;
+
+use serde::de::{DeserializeOwned, Error as _};
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::{
+    bump::{BumpMap, ImageNormalMap, NoiseBump, RippleBump},
+    matrix::Matrix,
+    pattern::*,
+    scene::{scene_error, Scene, SceneError},
+    texture::{
+        CubeMapPattern, Filter, ImageTexture, TextureMapPattern, UvAlignCheck, UvCheckers,
+        UvPattern, Wrap,
+    },
+};
+
+// Scenes are written as plain JSON. Patterns, UV patterns and bump maps are
+// trait objects, so each writes itself with a "type" tag naming its struct
+// and is rebuilt below by matching on that tag.
+
+pub fn scene_to_json(scene: &Scene) -> Result<String, SceneError> {
+    let _sharing = shared::Scope::begin();
+    return serde_json::to_string_pretty(scene).map_err(|e| json_error(&e));
+}
+
+pub fn scene_from_json(json: &str) -> Result<Scene, SceneError> {
+    let scene: Scene = {
+        let _sharing = shared::Scope::begin();
+        serde_json::from_str(json).map_err(|e| json_error(&e))?
+    };
+
+    check_transform(&scene.camera.transform)?;
+    for object in &scene.world.objects {
+        check_transform(&object.transform)?;
+    }
+    return Ok(scene);
+}
+
+// serde_json ends its messages with the position; keep the line separately,
+// as the YAML loader does.
+fn json_error(e: &serde_json::Error) -> SceneError {
+    let text = e.to_string();
+    let message = match text.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_string(),
+        None => text,
+    };
+    return SceneError {
+        line: if e.line() > 0 { Some(e.line()) } else { None },
+        message,
+    };
+}
+
+// Serializes `value` and adds the "type" tag to it.
+pub fn tagged<T: Serialize>(kind: &str, value: &T) -> Result<Value, SceneError> {
+    let mut json = serde_json::to_value(value).map_err(|e| json_error(&e))?;
+    match json.as_object_mut() {
+        Some(fields) => {
+            fields.insert("type".to_string(), Value::String(kind.to_string()));
+            Ok(json)
+        }
+        None => Err(scene_error(&format!(
+            "'{}' did not serialize to an object",
+            kind
+        ))),
+    }
+}
+
+// Every transform is a 4x4 matrix; anything else would panic when applied.
+fn check_transform(m: &Matrix<f64>) -> Result<(), SceneError> {
+    if m.data.len() != 4 || m.data.iter().any(|row| row.len() != 4) {
+        return Err(scene_error(&format!(
+            "transforms must be 4x4 matrices, found {} rows of {:?} numbers",
+            m.data.len(),
+            m.data.iter().map(|row| row.len()).collect::<Vec<_>>()
+        )));
+    }
+    return Ok(());
+}
+
+fn kind(json: &Value) -> Result<&str, SceneError> {
+    return json
+        .get("type")
+        .and_then(|t| t.as_str())
+        .ok_or_else(|| scene_error("missing \"type\""));
+}
+
+fn boxed<T: DeserializeOwned + 'static>(json: &Value) -> Result<Box<T>, SceneError> {
+    return serde_json::from_value(json.clone())
+        .map(Box::new)
+        .map_err(|e| json_error(&e));
+}
+
+pub fn pattern_from_json(json: &Value) -> Result<Box<dyn Pattern>, SceneError> {
+    let pattern: Box<dyn Pattern> = match kind(json)? {
+        "test" => boxed::<TestPattern>(json)?,
+        "solid" => boxed::<SolidPattern>(json)?,
+        "stripes" => boxed::<StripePattern>(json)?,
+        "gradient" => boxed::<GradientPattern>(json)?,
+        "rings" => boxed::<RingPattern>(json)?,
+        "checkers" => boxed::<CheckerPattern>(json)?,
+        "radial-gradient" => boxed::<RadialGradientPattern>(json)?,
+        "ring-gradient" => boxed::<RingGradientPattern>(json)?,
+        "polka-dots" => boxed::<PolkaDotPattern>(json)?,
+        "hexagons" => boxed::<HexPattern>(json)?,
+        "blended" => boxed::<BlendedPattern>(json)?,
+        "perturbed" => boxed::<PerturbedPattern>(json)?,
+        "turbulence" => boxed::<TurbulencePattern>(json)?,
+        "marble" => boxed::<MarblePattern>(json)?,
+        "wood" => boxed::<WoodPattern>(json)?,
+        "texture-map" => boxed::<TextureMapPattern>(json)?,
+        "cube-map" => boxed::<CubeMapPattern>(json)?,
+        other => return Err(scene_error(&format!("unknown pattern type '{}'", other))),
+    };
+    check_transform(pattern.transform())?;
+    return Ok(pattern);
+}
+
+pub fn uv_pattern_from_json(json: &Value) -> Result<Box<dyn UvPattern>, SceneError> {
+    return Ok(match kind(json)? {
+        "checkers" => boxed::<UvCheckers>(json)?,
+        "align-check" => boxed::<UvAlignCheck>(json)?,
+        "image" => Box::new(image_from_json(json)?),
+        other => return Err(scene_error(&format!("unknown UV pattern type '{}'", other))),
+    });
+}
+
+// Images saved with a path are read back from that file; ones made in
+// memory carry their pixels, which have to fill the stated size.
+fn image_from_json(json: &Value) -> Result<ImageTexture, SceneError> {
+    let texture = match json.get("path") {
+        Some(path) => {
+            let path: PathBuf = serde_json::from_value(path.clone()).map_err(|e| json_error(&e))?;
+            let loaded = ImageTexture::load(&path)
+                .map_err(|e| scene_error(&format!("cannot read {}: {}", path.display(), e)))?;
+            let saved = serde_json::from_value::<ImageSettings>(json.clone())
+                .map_err(|e| json_error(&e))?;
+            ImageTexture {
+                filter: saved.filter,
+                wrap: saved.wrap,
+                ..loaded
+            }
+        }
+        None => *boxed::<ImageTexture>(json)?,
+    };
+
+    let (width, height) = (texture.canvas.width(), texture.canvas.height());
+    if width == 0 || height == 0 {
+        return Err(scene_error(&format!(
+            "image texture is empty ({}x{})",
+            width, height
+        )));
+    }
+    if texture.canvas.pixels.len() != width * height * 3 {
+        return Err(scene_error(&format!(
+            "a {}x{} image needs {} samples, found {}",
+            width,
+            height,
+            width * height * 3,
+            texture.canvas.pixels.len()
+        )));
+    }
+    return Ok(texture);
+}
+
+#[derive(Deserialize)]
+struct ImageSettings {
+    filter: Filter,
+    wrap: Wrap,
+}
+
+pub fn bump_from_json(json: &Value) -> Result<Box<dyn BumpMap>, SceneError> {
+    let bump: Box<dyn BumpMap> = match kind(json)? {
+        "noise" => boxed::<NoiseBump>(json)?,
+        "ripples" => boxed::<RippleBump>(json)?,
+        "normal-map" => boxed::<ImageNormalMap>(json)?,
+        other => return Err(scene_error(&format!("unknown bump map type '{}'", other))),
+    };
+    check_transform(bump.transform())?;
+    return Ok(bump);
+}
+
+impl Serialize for dyn Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json()
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn Pattern> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        pattern_from_json(&Value::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for dyn UvPattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json()
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn UvPattern> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        uv_pattern_from_json(&Value::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for dyn BumpMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json()
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn BumpMap> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        bump_from_json(&Value::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+// Materials share patterns and bump maps through `Arc`s. While a scene is
+// being written, the first use of each shared value is written in full
+// under an id and later uses name only the id; reading rebuilds one `Arc`
+// per id, so materials that shared a pattern before saving still do.
+pub mod shared {
+    use std::any::Any;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    thread_local! {
+        static WRITTEN: RefCell<Option<HashMap<usize, usize>>> = const { RefCell::new(None) };
+        static READ: RefCell<Option<HashMap<usize, Box<dyn Any>>>> = const { RefCell::new(None) };
+    }
+
+    // Ids are only given out while a scope is open. Without one every use
+    // is written in full, as if nothing were shared.
+    pub struct Scope;
+
+    impl Scope {
+        pub fn begin() -> Scope {
+            WRITTEN.with(|w| *w.borrow_mut() = Some(HashMap::new()));
+            READ.with(|r| *r.borrow_mut() = Some(HashMap::new()));
+            return Scope;
+        }
+    }
+
+    impl Drop for Scope {
+        fn drop(&mut self) {
+            WRITTEN.with(|w| *w.borrow_mut() = None);
+            READ.with(|r| *r.borrow_mut() = None);
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Written<'a, T: ?Sized + Serialize> {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        shared: Option<usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        value: Option<&'a T>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(bound(deserialize = "Box<T>: Deserialize<'de>"))]
+    struct Read<T: ?Sized> {
+        shared: Option<usize>,
+        value: Option<Box<T>>,
+    }
+
+    pub fn serialize<T, S>(value: &Option<Arc<T>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        let value = match value {
+            Some(value) => value,
+            None => return serializer.serialize_none(),
+        };
+
+        let address = Arc::as_ptr(value) as *const () as usize;
+        let (shared, first) = WRITTEN.with(|w| match w.borrow_mut().as_mut() {
+            Some(ids) => match ids.get(&address) {
+                Some(&id) => (Some(id), false),
+                None => {
+                    let id = ids.len();
+                    ids.insert(address, id);
+                    (Some(id), true)
+                }
+            },
+            None => (None, true),
+        });
+
+        return Written {
+            shared,
+            value: if first { Some(&**value) } else { None },
+        }
+        .serialize(serializer);
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<Arc<T>>, D::Error>
+    where
+        T: ?Sized + 'static,
+        Box<T>: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let read = match Option::<Read<T>>::deserialize(deserializer)? {
+            Some(read) => read,
+            None => return Ok(None),
+        };
+
+        let value: Arc<T> = match (read.shared, read.value) {
+            (_, Some(value)) => Arc::from(value),
+            (Some(id), None) => READ
+                .with(|r| {
+                    r.borrow()
+                        .as_ref()
+                        .and_then(|seen| seen.get(&id))
+                        .and_then(|seen| seen.downcast_ref::<Arc<T>>())
+                        .cloned()
+                })
+                .ok_or_else(|| D::Error::custom(format!("unknown shared id {}", id)))?,
+            (None, None) => return Err(D::Error::custom("missing \"value\"")),
+        };
+
+        if let Some(id) = read.shared {
+            READ.with(|r| {
+                if let Some(seen) = r.borrow_mut().as_mut() {
+                    seen.entry(id).or_insert_with(|| Box::new(value.clone()));
+                }
+            });
+        }
+        return Ok(Some(value));
+    }
+}
@@ -1,13 +1,26 @@
+pub mod aov;
+pub mod bump;
 pub mod camera;
 pub mod canvas;
 pub mod color;
+pub mod diagnostics;
+pub mod json;
+pub mod library;
 pub mod light;
 pub mod material;
 pub mod matrix;
+pub mod media;
+pub mod microfacet;
+pub mod noise;
 pub mod pattern;
+pub mod picking;
 pub mod ray;
+pub mod scene;
 pub mod shapes;
+pub mod texture;
+pub mod tonemap;
 pub mod transforms;
 pub mod tuple;
 pub mod utils;
 pub mod world;
+pub mod yaml;
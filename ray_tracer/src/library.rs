@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use crate::{
+    color::color,
+    material::{default_material, microfacet_material, Dispersion, Material},
+};
+
+// Materials looked up by name. `material_library()` comes stocked with the
+// presets below; scenes can add or replace entries of their own.
+#[derive(Clone)]
+pub struct MaterialLibrary {
+    materials: HashMap<String, Material>,
+}
+
+impl MaterialLibrary {
+    pub fn new() -> Self {
+        Self {
+            materials: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, name: &str, material: Material) {
+        self.materials.insert(name.to_string(), material);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Material> {
+        self.materials.get(name).cloned()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.materials.contains_key(name)
+    }
+
+    // Sorted, so listings are stable.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.materials.keys().map(|n| n.as_str()).collect();
+        names.sort();
+        return names;
+    }
+}
+
+impl Default for MaterialLibrary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn material_library() -> MaterialLibrary {
+    let mut library = MaterialLibrary::new();
+    library.insert("glass", glass());
+    library.insert("water", water());
+    library.insert("diamond", diamond());
+    library.insert("air_bubble", air_bubble());
+    library.insert("mirror", mirror());
+    library.insert("chrome", chrome());
+    library.insert("gold", gold());
+    library.insert("matte", matte());
+    library.insert("plastic", plastic());
+    return library;
+}
+
+// Transparent dielectrics get most of their look from what they reflect and
+// refract, so their own color, ambient and diffuse are kept low. Whatever
+// they don't reflect passes through.
+fn clear(refractive_index: f64) -> Material {
+    Material {
+        color: color(0.1, 0.1, 0.1),
+        ambient: 0.,
+        diffuse: 0.1,
+        specular: 1.,
+        shininess: 300.,
+        reflective: 0.9,
+        transparency: 1.,
+        refractive_index,
+        ..default_material()
+    }
+}
+
+pub fn glass() -> Material {
+    clear(1.5)
+}
+
+pub fn water() -> Material {
+    Material {
+        color: color(0., 0.05, 0.1),
+        reflective: 0.5,
+        absorption: color(0.45, 0.06, 0.02),
+        ..clear(1.333)
+    }
+}
+
+// Diamond's fire comes from its dispersion, so it is traced per channel.
+pub fn diamond() -> Material {
+    Material {
+        dispersion: Dispersion::Abbe(55.3),
+        ..clear(2.417)
+    }
+}
+
+// Air seen from inside a denser medium, e.g. a bubble in water or glass.
+pub fn air_bubble() -> Material {
+    Material {
+        color: color(0., 0., 0.),
+        diffuse: 0.,
+        ..clear(1.000293)
+    }
+}
+
+pub fn mirror() -> Material {
+    Material {
+        color: color(0., 0., 0.),
+        ambient: 0.,
+        diffuse: 0.,
+        specular: 1.,
+        shininess: 300.,
+        reflective: 1.,
+        ..default_material()
+    }
+}
+
+// Metals use the microfacet model so their reflections take on their color.
+pub fn chrome() -> Material {
+    microfacet_material(color(0.55, 0.556, 0.554), 1., 0.1, 1.5)
+}
+
+pub fn gold() -> Material {
+    microfacet_material(color(1., 0.766, 0.336), 1., 0.2, 1.5)
+}
+
+pub fn matte() -> Material {
+    Material {
+        color: color(0.8, 0.8, 0.8),
+        diffuse: 0.9,
+        specular: 0.,
+        ..default_material()
+    }
+}
+
+pub fn plastic() -> Material {
+    Material {
+        color: color(0.8, 0.1, 0.1),
+        diffuse: 0.8,
+        specular: 0.5,
+        shininess: 100.,
+        reflective: 0.05,
+        refractive_index: 1.46,
+        ..default_material()
+    }
+}
@@ -1,12 +1,19 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    color::{black, Color},
-    material::Material,
+    color::{black, color, Color},
+    material::{Material, ShadingModel},
+    microfacet::{base_reflectance, distribution_ggx, fresnel_schlick, geometry_smith},
     ray::ray,
     shapes::object::Object,
     tuple::*,
+    utils::EPSILON,
     world::World,
 };
 
+#[derive(Serialize, Deserialize)]
 pub struct PointLight {
     pub position: Tuple,
     pub intensity: Color,
@@ -28,10 +35,7 @@ pub fn lighting(
     normalv: Tuple,
     in_shadow: bool,
 ) -> Color {
-    let color = match &material.pattern {
-        Some(pattern) => pattern.at_object(object, p),
-        None => material.color,
-    };
+    let color = material.color_at(object, p);
 
     let effective_color = color * light.intensity;
     let ambient = effective_color * material.ambient;
@@ -41,6 +45,42 @@ pub fn lighting(
     };
 
     let lightv = (light.position - p).normalize();
+
+    return match material.model {
+        ShadingModel::Phong => {
+            let specular = material.specular_at(object, p);
+            ambient
+                + phong(
+                    material,
+                    specular,
+                    light,
+                    effective_color,
+                    lightv,
+                    eyev,
+                    normalv,
+                )
+        }
+        ShadingModel::Microfacet { metallic, ior, .. } => {
+            let brdf = Microfacet {
+                base_color: color,
+                metallic,
+                roughness: material.roughness_at(object, p),
+                ior,
+            };
+            ambient + brdf.direct(light, lightv, eyev, normalv)
+        }
+    };
+}
+
+fn phong(
+    material: &Material,
+    specular_strength: f64,
+    light: &PointLight,
+    effective_color: Color,
+    lightv: Tuple,
+    eyev: Tuple,
+    normalv: Tuple,
+) -> Color {
     let diffuse: Color;
     let specular: Color;
 
@@ -58,11 +98,46 @@ pub fn lighting(
             specular = black();
         } else {
             let factor = reflect_dot_eye.powf(material.shininess);
-            specular = light.intensity * material.specular * factor;
+            specular = light.intensity * specular_strength * factor;
         }
     }
 
-    return ambient + diffuse + specular;
+    return diffuse + specular;
+}
+
+struct Microfacet {
+    base_color: Color,
+    metallic: f64,
+    roughness: f64,
+    ior: f64,
+}
+
+impl Microfacet {
+    // Cook-Torrance with a GGX distribution, Smith geometry and Schlick's
+    // Fresnel. The light's intensity is taken as the irradiance on a surface
+    // facing it, so the BRDF is scaled by PI to stay as bright as Phong.
+    fn direct(&self, light: &PointLight, lightv: Tuple, eyev: Tuple, normalv: Tuple) -> Color {
+        let n_dot_l = normalv.dot(lightv);
+        let n_dot_v = normalv.dot(eyev);
+        if n_dot_l <= 0. || n_dot_v <= 0. {
+            return black();
+        }
+
+        let halfv = (lightv + eyev).normalize();
+        let n_dot_h = normalv.dot(halfv).max(0.);
+        let v_dot_h = eyev.dot(halfv).max(0.);
+
+        let f0 = base_reflectance(self.base_color, self.metallic, self.ior);
+        let fresnel = fresnel_schlick(v_dot_h, f0);
+        let d = distribution_ggx(n_dot_h, self.roughness);
+        let g = geometry_smith(n_dot_v, n_dot_l, self.roughness);
+
+        let specular = fresnel * (d * g / (4. * n_dot_l * n_dot_v));
+        let k_d = (color(1., 1., 1.) - fresnel) * (1. - self.metallic);
+        let diffuse = k_d * self.base_color * (1. / PI);
+
+        return (diffuse + specular) * light.intensity * (n_dot_l * PI);
+    }
 }
 
 pub fn is_shadowed(world: &World, point: Tuple) -> bool {
@@ -78,3 +153,39 @@ pub fn is_shadowed(world: &World, point: Tuple) -> bool {
         _ => false,
     };
 }
+
+// Fraction of the light that reaches `point`, letting transparent objects
+// between it and the light pass their `transparency` through.
+pub fn light_transmittance(world: &World, point: Tuple) -> f64 {
+    let v = world.light.position - point;
+    let distance = v.magnitude();
+    let direction = v.normalize();
+
+    let r = ray(point, direction);
+    let intersections = world.intersect(&r);
+
+    // Closed objects are hit once on the way in and once on the way out, so
+    // each one filters the light once per span it occupies. `inside` holds
+    // the objects the ray is in, and whether that span was counted yet.
+    let mut inside: Vec<(&Object, bool)> = vec![];
+    let mut transmittance = 1.;
+    for i in intersections.locations.iter().filter(|i| i.t < distance) {
+        let in_front = i.t > EPSILON;
+        match inside.iter().position(|(o, _)| std::ptr::eq(*o, i.object)) {
+            Some(index) => {
+                let (_, counted) = inside.remove(index);
+                if in_front && !counted {
+                    transmittance *= i.object.material.transparency_at(i.object, r.position(i.t));
+                }
+            }
+            None => {
+                if in_front {
+                    transmittance *= i.object.material.transparency_at(i.object, r.position(i.t));
+                }
+                inside.push((i.object, in_front));
+            }
+        }
+    }
+
+    return transmittance;
+}
@@ -1,15 +1,68 @@
-use crate::{color::*, pattern::Pattern};
+use std::sync::Arc;
 
+use serde::{Deserialize, Serialize};
+
+use crate::{bump::BumpMap, color::*, pattern::Pattern, shapes::object::Object, tuple::Tuple};
+
+// Wavelengths (in micrometres) of the Fraunhofer C, d and F lines, which
+// define the Abbe number and stand in for the red, green and blue channels.
+const LAMBDA_C: f64 = 0.6563;
+const LAMBDA_D: f64 = 0.5876;
+const LAMBDA_F: f64 = 0.4861;
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Dispersion {
+    None,
+    Abbe(f64),
+    PerChannel(f64, f64, f64),
+}
+
+// How direct light is reflected off a surface. `Microfacet` uses `color` (or
+// the pattern) as its base color and ignores `diffuse`, `specular`,
+// `shininess` and `reflective`: Fresnel decides how strongly it reflects.
+// Its own `roughness` stands in for the material's, so `roughness_at` and
+// `roughness_pattern` apply to it as well.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ShadingModel {
+    Phong,
+    Microfacet {
+        metallic: f64,
+        roughness: f64,
+        ior: f64,
+    },
+}
+
+// Patterns and bumps are shared, so clones of a material all point at the
+// same pattern objects.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Material {
     pub color: Color,
     pub ambient: f64,
     pub diffuse: f64,
     pub specular: f64,
     pub shininess: f64,
-    pub pattern: Option<Box<dyn Pattern>>,
+    #[serde(default, with = "crate::json::shared")]
+    pub pattern: Option<Arc<dyn Pattern>>,
     pub reflective: f64,
     pub transparency: f64,
     pub refractive_index: f64,
+    pub absorption: Color,
+    pub dispersion: Dispersion,
+    pub model: ShadingModel,
+    pub roughness: f64,
+    pub glossy_samples: usize,
+    // Per-point overrides for the scalar properties above. Each pattern's
+    // color is read as a single value by averaging its channels.
+    #[serde(default, with = "crate::json::shared")]
+    pub reflective_pattern: Option<Arc<dyn Pattern>>,
+    #[serde(default, with = "crate::json::shared")]
+    pub specular_pattern: Option<Arc<dyn Pattern>>,
+    #[serde(default, with = "crate::json::shared")]
+    pub transparency_pattern: Option<Arc<dyn Pattern>>,
+    #[serde(default, with = "crate::json::shared")]
+    pub roughness_pattern: Option<Arc<dyn Pattern>>,
+    #[serde(default, with = "crate::json::shared")]
+    pub bump: Option<Arc<dyn BumpMap>>,
 }
 
 pub fn default_material() -> Material {
@@ -23,18 +76,131 @@ pub fn default_material() -> Material {
         reflective: 0.0,
         transparency: 0.0,
         refractive_index: 1.0,
+        absorption: color(0., 0., 0.),
+        dispersion: Dispersion::None,
+        model: ShadingModel::Phong,
+        roughness: 0.,
+        glossy_samples: 8,
+        reflective_pattern: None,
+        specular_pattern: None,
+        transparency_pattern: None,
+        roughness_pattern: None,
+        bump: None,
     }
 }
 pub fn material() -> Material {
     default_material()
 }
 
-impl Clone for Material {
-    fn clone(&self) -> Self {
-        Self {
-            pattern: None,
-            ..*self
+pub fn microfacet_material(base_color: Color, metallic: f64, roughness: f64, ior: f64) -> Material {
+    Material {
+        color: base_color,
+        model: ShadingModel::Microfacet {
+            metallic,
+            roughness,
+            ior,
+        },
+        ..default_material()
+    }
+}
+
+impl Material {
+    // The surface color at `world_point`, taken from the pattern if present.
+    pub fn color_at(&self, object: &Object, world_point: Tuple) -> Color {
+        match &self.pattern {
+            Some(pattern) => pattern.at_object(object, world_point),
+            None => self.color,
+        }
+    }
+
+    pub fn reflective_at(&self, object: &Object, world_point: Tuple) -> f64 {
+        scalar_at(
+            &self.reflective_pattern,
+            self.reflective,
+            object,
+            world_point,
+        )
+    }
+
+    // How much of the reflected color reaches the surface at `world_point`.
+    // A microfacet surface reflects fully unless `reflective_pattern` masks
+    // it out, since Fresnel already sets the strength.
+    pub fn reflection_scale_at(&self, object: &Object, world_point: Tuple) -> f64 {
+        match (self.model, &self.reflective_pattern) {
+            (ShadingModel::Microfacet { .. }, None) => 1.,
+            _ => self.reflective_at(object, world_point),
+        }
+    }
+
+    pub fn specular_at(&self, object: &Object, world_point: Tuple) -> f64 {
+        scalar_at(&self.specular_pattern, self.specular, object, world_point)
+    }
+
+    pub fn transparency_at(&self, object: &Object, world_point: Tuple) -> f64 {
+        scalar_at(
+            &self.transparency_pattern,
+            self.transparency,
+            object,
+            world_point,
+        )
+    }
+
+    pub fn roughness_at(&self, object: &Object, world_point: Tuple) -> f64 {
+        let roughness = match self.model {
+            ShadingModel::Phong => self.roughness,
+            ShadingModel::Microfacet { roughness, .. } => roughness,
+        };
+        scalar_at(&self.roughness_pattern, roughness, object, world_point)
+    }
+
+    pub fn is_dispersive(&self) -> bool {
+        self.dispersion != Dispersion::None
+    }
+
+    // Refractive index seen by a single color channel. With an Abbe number
+    // the `refractive_index` is taken as n_d and the other channels follow
+    // a Cauchy fit through n_F - n_C = (n_d - 1) / V.
+    pub fn refractive_index_for(&self, channel: Channel) -> f64 {
+        let n_d = self.refractive_index;
+        match self.dispersion {
+            Dispersion::None => n_d,
+            Dispersion::PerChannel(r, g, b) => color(r, g, b).channel(channel),
+            Dispersion::Abbe(v) => {
+                let lambda = match channel {
+                    Channel::Red => LAMBDA_C,
+                    Channel::Green => LAMBDA_D,
+                    Channel::Blue => LAMBDA_F,
+                };
+                let b = (n_d - 1.) / v / (LAMBDA_F.powi(-2) - LAMBDA_C.powi(-2));
+                n_d + b * (lambda.powi(-2) - LAMBDA_D.powi(-2))
+            }
+        }
+    }
+
+    // Beer–Lambert attenuation of light that travelled `distance` through
+    // the inside of this material.
+    pub fn attenuation(&self, distance: f64) -> Color {
+        let Color { r, g, b } = self.absorption;
+        color(
+            (-r * distance).exp(),
+            (-g * distance).exp(),
+            (-b * distance).exp(),
+        )
+    }
+}
+
+fn scalar_at(
+    pattern: &Option<Arc<dyn Pattern>>,
+    value: f64,
+    object: &Object,
+    world_point: Tuple,
+) -> f64 {
+    match pattern {
+        Some(pattern) => {
+            let c = pattern.at_object(object, world_point);
+            (c.r + c.g + c.b) / 3.
         }
+        None => value,
     }
 }
 
@@ -47,6 +213,27 @@ impl PartialEq for Material {
             && self.specular == other.specular
             && self.reflective == other.reflective
             && self.transparency == other.transparency
-            && self.refractive_index == other.refractive_index;
+            && self.refractive_index == other.refractive_index
+            && self.absorption == other.absorption
+            && self.dispersion == other.dispersion
+            && self.model == other.model
+            && self.roughness == other.roughness
+            && self.glossy_samples == other.glossy_samples
+            && same_shared(&self.pattern, &other.pattern)
+            && same_shared(&self.reflective_pattern, &other.reflective_pattern)
+            && same_shared(&self.specular_pattern, &other.specular_pattern)
+            && same_shared(&self.transparency_pattern, &other.transparency_pattern)
+            && same_shared(&self.roughness_pattern, &other.roughness_pattern)
+            && same_shared(&self.bump, &other.bump);
+    }
+}
+
+// Patterns can't be compared by value, so two slots are only equal when
+// both are empty or both hold the very same pattern.
+fn same_shared<T: ?Sized>(a: &Option<Arc<T>>, b: &Option<Arc<T>>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+        _ => false,
     }
 }
@@ -1,12 +1,14 @@
 use crate::transforms::*;
 use crate::tuple::*;
 use crate::utils::*;
+use serde::{Deserialize, Serialize};
 use std::cmp::PartialOrd;
 use std::fmt;
 use std::fmt::Display;
 use std::ops::{Add, Index, Mul, Neg, Sub};
+use std::sync::RwLock;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Matrix<T> {
     pub data: Vec<Vec<T>>,
 }
@@ -290,3 +292,41 @@ pub fn identity() -> Matrix<f64> {
         vec![0., 0., 0., 1.],
     ])
 }
+
+// The inverse of a transform that is read far more often than it changes,
+// such as a pattern's. It is worked out the first time it is needed and
+// again only if the transform has been replaced since.
+#[derive(Default)]
+pub struct InverseCache {
+    cached: RwLock<Option<(Matrix<f64>, Matrix<f64>)>>,
+}
+
+impl InverseCache {
+    // `t` multiplied by the inverse of `m`.
+    pub fn apply(&self, m: &Matrix<f64>, t: Tuple) -> Tuple {
+        if let Some((source, inverse)) = &*self.cached.read().unwrap() {
+            if source.data == m.data {
+                return inverse * t;
+            }
+        }
+
+        let inverse = m.inverse().unwrap();
+        let result = &inverse * t;
+        *self.cached.write().unwrap() = Some((m.clone(), inverse));
+        return result;
+    }
+}
+
+impl Clone for InverseCache {
+    fn clone(&self) -> Self {
+        InverseCache {
+            cached: RwLock::new(self.cached.read().unwrap().clone()),
+        }
+    }
+}
+
+impl fmt::Debug for InverseCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("InverseCache")
+    }
+}
@@ -0,0 +1,144 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color::{black, color, Color},
+    light::light_transmittance,
+    ray::{ray, Ray},
+    shapes::object::Object,
+    tuple::Tuple,
+    world::World,
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct Fog {
+    pub color: Color,
+    pub density: f64,
+}
+
+impl Fog {
+    pub fn transmittance(&self, distance: f64) -> f64 {
+        if self.density <= 0. {
+            return 1.;
+        }
+        return (-self.density * distance).exp();
+    }
+
+    pub fn apply(&self, c: Color, distance: f64) -> Color {
+        let transmittance = self.transmittance(distance);
+        return c * transmittance + self.color * (1. - transmittance);
+    }
+}
+
+pub fn fog(color: Color, density: f64) -> Fog {
+    Fog { color, density }
+}
+
+// A homogeneous participating medium (smoke, dust, murky water) filling the
+// inside of `boundary`. The boundary is never shaded as a surface; it only
+// decides where along a ray the medium starts and stops.
+#[derive(Serialize, Deserialize)]
+pub struct Medium {
+    pub boundary: Object,
+    pub color: Color,
+    pub absorption: f64,
+    pub scattering: f64,
+    pub step_size: f64,
+}
+
+impl Medium {
+    pub fn extinction(&self) -> f64 {
+        self.absorption + self.scattering
+    }
+
+    // Returns the (enter, exit) `t` values of `r` inside the boundary,
+    // clipped to the part of the ray between its origin and `max_t`.
+    pub fn segments(&self, r: &Ray, max_t: f64) -> Vec<(f64, f64)> {
+        let xs = self.boundary.intersect(r);
+        let mut ts: Vec<f64> = xs.locations.iter().map(|i| i.t).collect();
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        return ts
+            .chunks(2)
+            .filter(|pair| pair.len() == 2)
+            .map(|pair| (pair[0].max(0.), pair[1].min(max_t)))
+            .filter(|(enter, exit)| exit > enter)
+            .collect();
+    }
+
+    pub fn transmittance(&self, r: &Ray, max_t: f64) -> f64 {
+        let length: f64 = self
+            .segments(r, max_t)
+            .iter()
+            .map(|(enter, exit)| exit - enter)
+            .sum();
+
+        return (-self.extinction() * length * r.direction.magnitude()).exp();
+    }
+
+    // Ray marches the medium in front of `background`, which is the color
+    // seen at `max_t` along `r`. Each step attenuates what lies behind it and
+    // adds light scattered towards the eye, using a shadow ray for the
+    // single scattering term.
+    pub fn march(&self, world: &World, r: &Ray, background: Color, max_t: f64) -> Color {
+        let sigma_t = self.extinction();
+        if sigma_t <= 0. {
+            return background;
+        }
+
+        let speed = r.direction.magnitude();
+        let mut result = background;
+
+        for (enter, exit) in self.segments(r, max_t).into_iter().rev() {
+            // a step size of zero would never get through the segment, so
+            // step sizes that aren't positive cross it in one step
+            let steps = if self.step_size > 0. {
+                ((exit - enter) * speed / self.step_size).ceil().max(1.)
+            } else {
+                1.
+            };
+            let dt = (exit - enter) / steps;
+            let step_transmittance = (-sigma_t * dt * speed).exp();
+
+            let mut t = exit - dt / 2.;
+            while t > enter {
+                let p = r.position(t);
+                let in_scattered = self.in_scattered(world, p);
+
+                result = result * step_transmittance
+                    + in_scattered * (self.scattering / sigma_t * (1. - step_transmittance));
+                t -= dt;
+            }
+        }
+
+        return result;
+    }
+
+    fn in_scattered(&self, world: &World, p: Tuple) -> Color {
+        let light = &world.light;
+        let v = light.position - p;
+        let distance = v.magnitude();
+        let to_light = ray(p, v.normalize());
+
+        let occlusion = light_transmittance(world, p);
+        if occlusion == 0. {
+            return black();
+        }
+
+        let attenuation = self.transmittance(&to_light, distance);
+        let phase = 1. / (4. * PI);
+
+        return self.color * light.intensity * (occlusion * attenuation * phase);
+    }
+}
+
+pub fn medium(boundary: Object, absorption: f64, scattering: f64) -> Medium {
+    Medium {
+        boundary,
+        color: color(1., 1., 1.),
+        absorption,
+        scattering,
+        step_size: 0.05,
+    }
+}
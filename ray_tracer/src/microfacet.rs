@@ -0,0 +1,94 @@
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::{
+    color::{color, Color},
+    tuple::{vector, Tuple},
+};
+
+// Below this the GGX lobe collapses to a spike that point lights can never
+// hit, so highlights are evaluated with at least this roughness.
+const MIN_ROUGHNESS: f64 = 0.02;
+
+// Trowbridge-Reitz (GGX) normal distribution function.
+pub fn distribution_ggx(n_dot_h: f64, roughness: f64) -> f64 {
+    let alpha = roughness.max(MIN_ROUGHNESS).powi(2);
+    let alpha2 = alpha * alpha;
+    let denominator = n_dot_h * n_dot_h * (alpha2 - 1.) + 1.;
+
+    return alpha2 / (PI * denominator * denominator);
+}
+
+// Smith's geometry term built from two Schlick-GGX masking functions, with
+// the `k` remapping used for analytic lights.
+pub fn geometry_smith(n_dot_v: f64, n_dot_l: f64, roughness: f64) -> f64 {
+    let k = (roughness + 1.).powi(2) / 8.;
+    let g1 = |x: f64| x / (x * (1. - k) + k);
+
+    return g1(n_dot_v) * g1(n_dot_l);
+}
+
+pub fn fresnel_schlick(cos_theta: f64, f0: Color) -> Color {
+    let factor = (1. - cos_theta.clamp(0., 1.)).powi(5);
+    return f0 + (color(1., 1., 1.) - f0) * factor;
+}
+
+// Reflectance at normal incidence: dielectrics derive it from their index of
+// refraction, metals tint it with their base color.
+pub fn base_reflectance(base_color: Color, metallic: f64, ior: f64) -> Color {
+    let dielectric = ((ior - 1.) / (ior + 1.)).powi(2);
+    let f0 = color(dielectric, dielectric, dielectric);
+
+    return f0 * (1. - metallic) + base_color * metallic;
+}
+
+// Two unit vectors that, together with `n`, form an orthonormal basis.
+pub fn orthonormal_basis(n: Tuple) -> (Tuple, Tuple) {
+    let helper = if n.x.abs() > 0.9 {
+        vector(0., 1., 0.)
+    } else {
+        vector(1., 0., 0.)
+    };
+    let tangent = helper.cross(n).normalize();
+    let bitangent = n.cross(tangent);
+
+    return (tangent, bitangent);
+}
+
+// Draws a microfacet normal around `n` distributed according to GGX.
+pub fn sample_ggx_half_vector<R: Rng>(n: Tuple, roughness: f64, rng: &mut R) -> Tuple {
+    let alpha = roughness.powi(2);
+    let u1: f64 = rng.gen();
+    let u2: f64 = rng.gen();
+
+    let theta = (alpha * (u1 / (1. - u1)).sqrt()).atan();
+    let phi = 2. * PI * u2;
+
+    let (tangent, bitangent) = orthonormal_basis(n);
+    let h = tangent * (theta.sin() * phi.cos())
+        + bitangent * (theta.sin() * phi.sin())
+        + n * theta.cos();
+
+    return h.normalize();
+}
+
+// Perturbs the unit vector `direction` by a random offset inside a sphere of
+// radius `roughness`, giving a simple glossy lobe for Phong materials.
+pub fn sample_lobe<R: Rng>(direction: Tuple, roughness: f64, rng: &mut R) -> Tuple {
+    loop {
+        let offset = vector(
+            rng.gen::<f64>() * 2. - 1.,
+            rng.gen::<f64>() * 2. - 1.,
+            rng.gen::<f64>() * 2. - 1.,
+        );
+        if offset.dot(offset) > 1. {
+            continue;
+        }
+
+        let jittered = direction + offset * roughness;
+        if jittered.dot(jittered) > 0. {
+            return jittered.normalize();
+        }
+    }
+}
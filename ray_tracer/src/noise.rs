@@ -0,0 +1,133 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::tuple::Tuple;
+
+// Ken Perlin's improved gradient noise. The permutation table is shuffled
+// from `seed`, so the same seed always yields the same noise field.
+// Only the seed is stored; the permutation is rebuilt from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "u64", into = "u64")]
+pub struct Perlin {
+    pub seed: u64,
+    permutation: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut permutation: Vec<usize> = (0..256).collect();
+        permutation.shuffle(&mut StdRng::seed_from_u64(seed));
+        permutation.extend_from_within(..);
+
+        Self { seed, permutation }
+    }
+
+    // Smoothly varying value in roughly -1..1 that is zero at every
+    // integer lattice point.
+    pub fn noise(&self, p: Tuple) -> f64 {
+        let p_ = &self.permutation;
+        let (xi, yi, zi) = (lattice(p.x), lattice(p.y), lattice(p.z));
+        let (x, y, z) = (p.x - p.x.floor(), p.y - p.y.floor(), p.z - p.z.floor());
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let a = p_[xi] + yi;
+        let aa = p_[a] + zi;
+        let ab = p_[a + 1] + zi;
+        let b = p_[xi + 1] + yi;
+        let ba = p_[b] + zi;
+        let bb = p_[b + 1] + zi;
+
+        return lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(p_[aa], x, y, z), grad(p_[ba], x - 1., y, z)),
+                lerp(
+                    u,
+                    grad(p_[ab], x, y - 1., z),
+                    grad(p_[bb], x - 1., y - 1., z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(p_[aa + 1], x, y, z - 1.),
+                    grad(p_[ba + 1], x - 1., y, z - 1.),
+                ),
+                lerp(
+                    u,
+                    grad(p_[ab + 1], x, y - 1., z - 1.),
+                    grad(p_[bb + 1], x - 1., y - 1., z - 1.),
+                ),
+            ),
+        );
+    }
+
+    // Sum of `octaves` layers of noise, each at twice the frequency and
+    // half the amplitude of the previous one.
+    pub fn fractal(&self, p: Tuple, octaves: u32) -> f64 {
+        return self.octaves(p, octaves, |n| n);
+    }
+
+    // Like `fractal`, but sums absolute values, giving the billowy look
+    // used for smoke, fire and marble veins. Always >= 0.
+    pub fn turbulence(&self, p: Tuple, octaves: u32) -> f64 {
+        return self.octaves(p, octaves, f64::abs);
+    }
+
+    fn octaves<F: Fn(f64) -> f64>(&self, p: Tuple, octaves: u32, f: F) -> f64 {
+        let mut total = 0.;
+        let mut frequency = 1.;
+        for _ in 0..octaves.max(1) {
+            total += f(self.noise(p * frequency)) / frequency;
+            frequency *= 2.;
+        }
+        return total;
+    }
+}
+
+impl From<u64> for Perlin {
+    fn from(seed: u64) -> Self {
+        Perlin::new(seed)
+    }
+}
+
+impl From<Perlin> for u64 {
+    fn from(noise: Perlin) -> Self {
+        noise.seed
+    }
+}
+
+pub fn perlin(seed: u64) -> Perlin {
+    Perlin::new(seed)
+}
+
+fn lattice(a: f64) -> usize {
+    (a.floor() as i64).rem_euclid(256) as usize
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+// Dot product of (x, y, z) with one of twelve gradient directions.
+fn grad(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+
+    let u = if h & 1 == 0 { u } else { -u };
+    let v = if h & 2 == 0 { v } else { -v };
+    return u + v;
+}
@@ -1,60 +1,197 @@
+use std::f64::consts::PI;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
 use crate::{
     color::{color, Color},
-    matrix::{identity, Matrix},
+    json::tagged,
+    matrix::{identity, InverseCache, Matrix},
+    noise::{perlin, Perlin},
+    scene::{scene_error, SceneError},
     shapes::object::Object,
-    tuple::Tuple,
+    tuple::{vector, Tuple},
 };
 
-pub trait Pattern: Send + Sync {
+pub trait Pattern: PatternClone + Send + Sync {
     fn transform(&self) -> &Matrix<f64>;
 
+    // Where the inverse of `transform` is kept between lookups.
+    fn inverse_cache(&self) -> &InverseCache;
+
     fn at_point(&self, point: Tuple) -> Color;
 
     fn at_object(&self, shape: &Object, world_point: Tuple) -> Color {
         let object_point = shape.transform.inverse().unwrap() * world_point;
-        let pattern_point = self.transform().inverse().unwrap() * object_point;
-        self.at_point(pattern_point)
+        self.at_parent_point(object_point)
+    }
+
+    // Evaluates this pattern at a point given in an enclosing pattern's
+    // space, so a nested pattern still honours its own transform.
+    fn at_parent_point(&self, parent_point: Tuple) -> Color {
+        self.at_point(self.inverse_cache().apply(self.transform(), parent_point))
+    }
+
+    // The patterns filling this one's slots, named after the slots, so
+    // checks like `World::validate` can reach nested patterns.
+    fn children(&self) -> Vec<(&str, &dyn Pattern)> {
+        vec![]
+    }
+
+    // What is wrong with this pattern's own settings, not counting its
+    // transform or its children, that would spoil a render.
+    fn problems(&self) -> Vec<String> {
+        vec![]
+    }
+
+    // The pattern as tagged JSON, see `json::pattern_from_json`. Patterns
+    // defined outside this crate can't be saved unless they override this.
+    fn to_json(&self) -> Result<Value, SceneError> {
+        Err(scene_error("this pattern cannot be serialized"))
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TestPattern {
     pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
 }
 impl Pattern for TestPattern {
     fn transform(&self) -> &Matrix<f64> {
         &self.transform
     }
 
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("test", self)
+    }
+
     fn at_point(&self, point: Tuple) -> Color {
         color(point.x, point.y, point.z)
     }
 }
 
-#[derive(Debug, Clone)]
+// A single color everywhere; what a plain `Color` becomes when it fills a
+// pattern slot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SolidPattern {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub color: Color,
+}
+impl Pattern for SolidPattern {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("solid", self)
+    }
+
+    fn at_point(&self, _point: Tuple) -> Color {
+        self.color
+    }
+}
+
+// Lets boxed patterns be cloned. Every `Pattern` that is `Clone` gets it.
+pub trait PatternClone {
+    fn clone_pattern(&self) -> Box<dyn Pattern>;
+}
+
+impl<P: Pattern + Clone + 'static> PatternClone for P {
+    fn clone_pattern(&self) -> Box<dyn Pattern> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Pattern> {
+    fn clone(&self) -> Self {
+        self.clone_pattern()
+    }
+}
+
+impl fmt::Debug for dyn Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pattern")
+            .field("transform", self.transform())
+            .finish_non_exhaustive()
+    }
+}
+
+// Anything that can fill a pattern slot: a plain color or another pattern.
+pub trait IntoPattern {
+    fn into_pattern(self) -> Box<dyn Pattern>;
+}
+
+impl IntoPattern for Color {
+    fn into_pattern(self) -> Box<dyn Pattern> {
+        Box::new(solid_pattern(self))
+    }
+}
+
+impl IntoPattern for Box<dyn Pattern> {
+    fn into_pattern(self) -> Box<dyn Pattern> {
+        self
+    }
+}
+
+impl<P: Pattern + 'static> IntoPattern for P {
+    fn into_pattern(self) -> Box<dyn Pattern> {
+        Box::new(self)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StripePattern {
     pub transform: Matrix<f64>,
-    pub a: Color,
-    pub b: Color,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
 }
 impl Pattern for StripePattern {
     fn transform(&self) -> &Matrix<f64> {
         &self.transform
     }
 
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn children(&self) -> Vec<(&str, &dyn Pattern)> {
+        vec![("a", &*self.a), ("b", &*self.b)]
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("stripes", self)
+    }
+
     fn at_point(&self, point: Tuple) -> Color {
         if point.x.floor() as i64 % 2 == 0 {
-            self.a
+            self.a.at_parent_point(point)
         } else {
-            self.b
+            self.b.at_parent_point(point)
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GradientPattern {
     pub transform: Matrix<f64>,
-    pub a: Color,
-    pub b: Color,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
 }
 
 impl Pattern for GradientPattern {
@@ -62,18 +199,36 @@ impl Pattern for GradientPattern {
         &self.transform
     }
 
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn children(&self) -> Vec<(&str, &dyn Pattern)> {
+        vec![("a", &*self.a), ("b", &*self.b)]
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("gradient", self)
+    }
+
     fn at_point(&self, point: Tuple) -> Color {
-        let disance = self.b - self.a;
         let fraction = point.x - point.x.floor();
 
-        return self.a + disance * fraction;
+        return blend(
+            self.a.at_parent_point(point),
+            self.b.at_parent_point(point),
+            fraction,
+        );
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RingPattern {
     pub transform: Matrix<f64>,
-    pub a: Color,
-    pub b: Color,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
 }
 
 impl Pattern for RingPattern {
@@ -81,20 +236,35 @@ impl Pattern for RingPattern {
         &self.transform
     }
 
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn children(&self) -> Vec<(&str, &dyn Pattern)> {
+        vec![("a", &*self.a), ("b", &*self.b)]
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("rings", self)
+    }
+
     fn at_point(&self, point: Tuple) -> Color {
         let distance = (point.x.powf(2.) + point.z.powf(2.)).sqrt();
-        if distance % 2. == 0. {
-            self.a
+        if distance.floor() as i64 % 2 == 0 {
+            self.a.at_parent_point(point)
         } else {
-            self.b
+            self.b.at_parent_point(point)
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheckerPattern {
     pub transform: Matrix<f64>,
-    pub a: Color,
-    pub b: Color,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
 }
 
 impl Pattern for CheckerPattern {
@@ -102,50 +272,591 @@ impl Pattern for CheckerPattern {
         &self.transform
     }
 
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn children(&self) -> Vec<(&str, &dyn Pattern)> {
+        vec![("a", &*self.a), ("b", &*self.b)]
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("checkers", self)
+    }
+
     fn at_point(&self, point: Tuple) -> Color {
-        let val = (point.x.abs() + point.y.abs() + point.z.abs()).floor();
-        if val % 2. == 0. {
-            self.a
+        let val = point.x.floor() + point.y.floor() + point.z.floor();
+        if val.rem_euclid(2.) == 0. {
+            self.a.at_parent_point(point)
         } else {
-            self.b
+            self.b.at_parent_point(point)
         }
     }
 }
 
+// Bands of `a` fading into `b` with distance from the origin, restarting
+// every unit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RadialGradientPattern {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+}
+
+impl Pattern for RadialGradientPattern {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn children(&self) -> Vec<(&str, &dyn Pattern)> {
+        vec![("a", &*self.a), ("b", &*self.b)]
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("radial-gradient", self)
+    }
+
+    fn at_point(&self, point: Tuple) -> Color {
+        let distance = vector(point.x, point.y, point.z).magnitude();
+        let fraction = distance - distance.floor();
+
+        return blend(
+            self.a.at_parent_point(point),
+            self.b.at_parent_point(point),
+            fraction,
+        );
+    }
+}
+
+// Rings around the y axis like `RingPattern`, but each band fades into the
+// next instead of switching abruptly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RingGradientPattern {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+}
+
+impl Pattern for RingGradientPattern {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn children(&self) -> Vec<(&str, &dyn Pattern)> {
+        vec![("a", &*self.a), ("b", &*self.b)]
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("ring-gradient", self)
+    }
+
+    fn at_point(&self, point: Tuple) -> Color {
+        let distance = (point.x.powi(2) + point.z.powi(2)).sqrt();
+        // a triangle wave: 0 at even distances, 1 at odd ones
+        let t = 1. - (distance.rem_euclid(2.) - 1.).abs();
+
+        return blend(
+            self.a.at_parent_point(point),
+            self.b.at_parent_point(point),
+            t,
+        );
+    }
+}
+
+// Dots of `a` centered in each unit cell, on a background of `b`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolkaDotPattern {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+    pub radius: f64,
+}
+
+impl Pattern for PolkaDotPattern {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn children(&self) -> Vec<(&str, &dyn Pattern)> {
+        vec![("a", &*self.a), ("b", &*self.b)]
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("polka-dots", self)
+    }
+
+    fn at_point(&self, point: Tuple) -> Color {
+        let offset = |a: f64| a - a.floor() - 0.5;
+        let from_center = vector(offset(point.x), offset(point.y), offset(point.z));
+
+        if from_center.magnitude() <= self.radius {
+            self.a.at_parent_point(point)
+        } else {
+            self.b.at_parent_point(point)
+        }
+    }
+}
+
+// Pointy-top hexagons of unit radius tiling the xz plane. Three slots are
+// needed so that no two neighbouring cells share a pattern.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HexPattern {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+    pub c: Box<dyn Pattern>,
+}
+
+impl HexPattern {
+    // Axial coordinates of the cell containing `point`.
+    pub fn cell(point: Tuple) -> (i64, i64) {
+        let q = 3_f64.sqrt() / 3. * point.x - point.z / 3.;
+        let r = 2. / 3. * point.z;
+        let s = -q - r;
+
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+
+        return (rq as i64, rr as i64);
+    }
+}
+
+impl Pattern for HexPattern {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn children(&self) -> Vec<(&str, &dyn Pattern)> {
+        vec![("a", &*self.a), ("b", &*self.b), ("c", &*self.c)]
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("hexagons", self)
+    }
+
+    fn at_point(&self, point: Tuple) -> Color {
+        let (q, r) = HexPattern::cell(point);
+
+        return match (q - r).rem_euclid(3) {
+            0 => self.a.at_parent_point(point),
+            1 => self.b.at_parent_point(point),
+            _ => self.c.at_parent_point(point),
+        };
+    }
+}
+
+// Mixes two patterns: `weight` 0 is all `a`, 1 is all `b`, and the default
+// 0.5 averages them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlendedPattern {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+    pub weight: f64,
+}
+
+impl Pattern for BlendedPattern {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn children(&self) -> Vec<(&str, &dyn Pattern)> {
+        vec![("a", &*self.a), ("b", &*self.b)]
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("blended", self)
+    }
+
+    fn at_point(&self, point: Tuple) -> Color {
+        blend(
+            self.a.at_parent_point(point),
+            self.b.at_parent_point(point),
+            self.weight,
+        )
+    }
+}
+
+// Jitters each point by a noise field before handing it to `inner`, which
+// breaks up the straight edges of stripes, rings and checkers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerturbedPattern {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub inner: Box<dyn Pattern>,
+    pub noise: Perlin,
+    pub amount: f64,
+    pub scale: f64,
+    pub octaves: u32,
+}
+
+impl Pattern for PerturbedPattern {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn children(&self) -> Vec<(&str, &dyn Pattern)> {
+        vec![("inner", &*self.inner)]
+    }
+
+    fn problems(&self) -> Vec<String> {
+        nonzero_scale(self.scale)
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("perturbed", self)
+    }
+
+    fn at_point(&self, point: Tuple) -> Color {
+        let q = point / self.scale;
+        let offset = vector(
+            self.noise.fractal(q, self.octaves),
+            self.noise.fractal(q + vector(5.2, 1.3, 2.8), self.octaves),
+            self.noise.fractal(q + vector(1.7, 9.2, 3.1), self.octaves),
+        );
+
+        self.inner.at_parent_point(point + offset * self.amount)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurbulencePattern {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+    pub noise: Perlin,
+    pub scale: f64,
+    pub octaves: u32,
+}
+
+impl Pattern for TurbulencePattern {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn children(&self) -> Vec<(&str, &dyn Pattern)> {
+        vec![("a", &*self.a), ("b", &*self.b)]
+    }
+
+    fn problems(&self) -> Vec<String> {
+        nonzero_scale(self.scale)
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("turbulence", self)
+    }
+
+    fn at_point(&self, point: Tuple) -> Color {
+        let t = self.noise.turbulence(point / self.scale, self.octaves);
+        blend(
+            self.a.at_parent_point(point),
+            self.b.at_parent_point(point),
+            t.min(1.),
+        )
+    }
+}
+
+// Veins running across x, bent by turbulence.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarblePattern {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+    pub noise: Perlin,
+    pub scale: f64,
+    pub octaves: u32,
+    pub turbulence: f64,
+}
+
+impl Pattern for MarblePattern {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn children(&self) -> Vec<(&str, &dyn Pattern)> {
+        vec![("a", &*self.a), ("b", &*self.b)]
+    }
+
+    fn problems(&self) -> Vec<String> {
+        nonzero_scale(self.scale)
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("marble", self)
+    }
+
+    fn at_point(&self, point: Tuple) -> Color {
+        let distortion = self.noise.turbulence(point / self.scale, self.octaves);
+        let t = 0.5 + 0.5 * ((point.x + self.turbulence * distortion) * PI).sin();
+        blend(
+            self.a.at_parent_point(point),
+            self.b.at_parent_point(point),
+            t,
+        )
+    }
+}
+
+// Growth rings around the y axis, made irregular with noise.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WoodPattern {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+    pub noise: Perlin,
+    pub scale: f64,
+    pub octaves: u32,
+    pub turbulence: f64,
+    pub rings: f64,
+}
+
+impl Pattern for WoodPattern {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn children(&self) -> Vec<(&str, &dyn Pattern)> {
+        vec![("a", &*self.a), ("b", &*self.b)]
+    }
+
+    fn problems(&self) -> Vec<String> {
+        nonzero_scale(self.scale)
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("wood", self)
+    }
+
+    fn at_point(&self, point: Tuple) -> Color {
+        let distortion = self.noise.fractal(point / self.scale, self.octaves);
+        let distance = (point.x.powi(2) + point.z.powi(2)).sqrt() + self.turbulence * distortion;
+        let t = (distance * self.rings).rem_euclid(1.);
+        blend(
+            self.a.at_parent_point(point),
+            self.b.at_parent_point(point),
+            t,
+        )
+    }
+}
+
+// Noise patterns divide points by their scale.
+fn nonzero_scale(scale: f64) -> Vec<String> {
+    if scale == 0. || !scale.is_finite() {
+        return vec![format!("scale must be finite and not 0, found {}", scale)];
+    }
+    return vec![];
+}
+
+fn blend(a: Color, b: Color, t: f64) -> Color {
+    a + (b - a) * t
+}
+
 pub fn test_pattern() -> TestPattern {
     TestPattern {
         transform: identity(),
+        inverse: InverseCache::default(),
     }
 }
 
-pub fn stripe_pattern(a: Color, b: Color) -> StripePattern {
+pub fn stripe_pattern<A: IntoPattern, B: IntoPattern>(a: A, b: B) -> StripePattern {
     StripePattern {
-        a,
-        b,
+        a: a.into_pattern(),
+        b: b.into_pattern(),
         transform: identity(),
+        inverse: InverseCache::default(),
     }
 }
 
-pub fn gradient_pattern(a: Color, b: Color) -> GradientPattern {
+pub fn gradient_pattern<A: IntoPattern, B: IntoPattern>(a: A, b: B) -> GradientPattern {
     GradientPattern {
-        a,
-        b,
+        a: a.into_pattern(),
+        b: b.into_pattern(),
         transform: identity(),
+        inverse: InverseCache::default(),
     }
 }
 
-pub fn ring_pattern(a: Color, b: Color) -> RingPattern {
+pub fn ring_pattern<A: IntoPattern, B: IntoPattern>(a: A, b: B) -> RingPattern {
     RingPattern {
-        a,
-        b,
+        a: a.into_pattern(),
+        b: b.into_pattern(),
         transform: identity(),
+        inverse: InverseCache::default(),
     }
 }
 
-pub fn checker_pattern(a: Color, b: Color) -> CheckerPattern {
+pub fn checker_pattern<A: IntoPattern, B: IntoPattern>(a: A, b: B) -> CheckerPattern {
     CheckerPattern {
-        a,
-        b,
+        a: a.into_pattern(),
+        b: b.into_pattern(),
+        transform: identity(),
+        inverse: InverseCache::default(),
+    }
+}
+
+pub fn radial_gradient_pattern<A: IntoPattern, B: IntoPattern>(
+    a: A,
+    b: B,
+) -> RadialGradientPattern {
+    RadialGradientPattern {
+        a: a.into_pattern(),
+        b: b.into_pattern(),
+        transform: identity(),
+        inverse: InverseCache::default(),
+    }
+}
+
+pub fn ring_gradient_pattern<A: IntoPattern, B: IntoPattern>(a: A, b: B) -> RingGradientPattern {
+    RingGradientPattern {
+        a: a.into_pattern(),
+        b: b.into_pattern(),
+        transform: identity(),
+        inverse: InverseCache::default(),
+    }
+}
+
+pub fn polka_dot_pattern<A: IntoPattern, B: IntoPattern>(a: A, b: B) -> PolkaDotPattern {
+    PolkaDotPattern {
+        a: a.into_pattern(),
+        b: b.into_pattern(),
+        radius: 0.25,
+        transform: identity(),
+        inverse: InverseCache::default(),
+    }
+}
+
+pub fn hex_pattern<A: IntoPattern, B: IntoPattern, C: IntoPattern>(a: A, b: B, c: C) -> HexPattern {
+    HexPattern {
+        a: a.into_pattern(),
+        b: b.into_pattern(),
+        c: c.into_pattern(),
+        transform: identity(),
+        inverse: InverseCache::default(),
+    }
+}
+
+pub fn solid_pattern(color: Color) -> SolidPattern {
+    SolidPattern {
+        transform: identity(),
+        inverse: InverseCache::default(),
+        color,
+    }
+}
+
+pub fn blended_pattern<A: IntoPattern, B: IntoPattern>(a: A, b: B) -> BlendedPattern {
+    BlendedPattern {
+        transform: identity(),
+        inverse: InverseCache::default(),
+        a: a.into_pattern(),
+        b: b.into_pattern(),
+        weight: 0.5,
+    }
+}
+
+pub fn perturbed_pattern(inner: Box<dyn Pattern>) -> PerturbedPattern {
+    PerturbedPattern {
+        transform: identity(),
+        inverse: InverseCache::default(),
+        inner,
+        noise: perlin(0),
+        amount: 0.2,
+        scale: 1.,
+        octaves: 3,
+    }
+}
+
+pub fn turbulence_pattern<A: IntoPattern, B: IntoPattern>(a: A, b: B) -> TurbulencePattern {
+    TurbulencePattern {
+        transform: identity(),
+        inverse: InverseCache::default(),
+        a: a.into_pattern(),
+        b: b.into_pattern(),
+        noise: perlin(0),
+        scale: 1.,
+        octaves: 4,
+    }
+}
+
+pub fn marble_pattern<A: IntoPattern, B: IntoPattern>(a: A, b: B) -> MarblePattern {
+    MarblePattern {
+        transform: identity(),
+        inverse: InverseCache::default(),
+        a: a.into_pattern(),
+        b: b.into_pattern(),
+        noise: perlin(0),
+        scale: 1.,
+        octaves: 4,
+        turbulence: 5.,
+    }
+}
+
+pub fn wood_pattern<A: IntoPattern, B: IntoPattern>(a: A, b: B) -> WoodPattern {
+    WoodPattern {
         transform: identity(),
+        inverse: InverseCache::default(),
+        a: a.into_pattern(),
+        b: b.into_pattern(),
+        noise: perlin(0),
+        scale: 1.,
+        octaves: 2,
+        turbulence: 0.1,
+        rings: 4.,
     }
 }
@@ -0,0 +1,143 @@
+use uuid::Uuid;
+
+use crate::{
+    camera::Camera,
+    ray::{ray, Ray},
+    shapes::object::Object,
+    tuple::Tuple,
+    utils::RECURSION_DEPTH,
+    world::{prepare_computations, refraction_direction, World},
+};
+
+// One place a ray crosses an object's surface.
+pub struct Pick<'a> {
+    pub object: &'a Object,
+    pub uuid: Uuid,
+    pub t: f64,
+    pub point: Tuple,
+    // Faces back along the ray, so it is flipped when `inside` is true.
+    pub normal: Tuple,
+    pub inside: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SecondaryKind {
+    Reflection,
+    Refraction,
+}
+
+// A reflection or refraction ray spawned at a hit, with what it hit in turn
+// and the rays spawned there.
+pub struct SecondaryRay<'a> {
+    pub kind: SecondaryKind,
+    pub ray: Ray,
+    pub hit: Option<Pick<'a>>,
+    pub spawned: Vec<SecondaryRay<'a>>,
+}
+
+// Everything the ray through the middle of a pixel runs into.
+pub struct PixelPick<'a> {
+    pub ray: Ray,
+    // Every surface in front of the camera, nearest first.
+    pub hits: Vec<Pick<'a>>,
+    // Only filled in when asked for, see `pick`.
+    pub secondary: Option<Vec<SecondaryRay<'a>>>,
+}
+
+impl<'a> PixelPick<'a> {
+    // The surface visible at the pixel.
+    pub fn nearest(&self) -> Option<&Pick<'a>> {
+        self.hits.first()
+    }
+}
+
+// Asks which objects lie under pixel (x, y). With `trace_secondary` the
+// reflection and refraction rays `shade_hit` would spawn from the nearest
+// hit are followed too, down to the same recursion depth. They follow the
+// perfect mirror and refraction directions: rough surfaces are not jittered
+// and dispersive materials bend every channel by their base index.
+pub fn pick<'a>(
+    camera: &Camera,
+    world: &'a World,
+    x: usize,
+    y: usize,
+    trace_secondary: bool,
+) -> Result<PixelPick<'a>, String> {
+    if x >= camera.hsize as usize || y >= camera.vsize as usize {
+        return Err(format!(
+            "pixel ({},{}) is outside the {}x{} image",
+            x, y, camera.hsize, camera.vsize
+        ));
+    }
+
+    let r = camera.ray_for_pixel(x as i16, y as i16);
+    let secondary = if trace_secondary {
+        Some(secondary_rays(world, &r, RECURSION_DEPTH))
+    } else {
+        None
+    };
+
+    return Ok(PixelPick {
+        hits: picks_along(world, &r),
+        ray: r,
+        secondary,
+    });
+}
+
+fn picks_along<'a>(world: &'a World, r: &Ray) -> Vec<Pick<'a>> {
+    let xs = world.intersect(r);
+    return xs
+        .locations
+        .iter()
+        .filter(|i| i.t > 0.)
+        .map(|i| {
+            let comps = prepare_computations(i, r, &xs);
+            Pick {
+                object: i.object,
+                uuid: i.object.uuid,
+                t: i.t,
+                point: comps.point,
+                normal: comps.normalv,
+                inside: comps.inside,
+            }
+        })
+        .collect();
+}
+
+// Mirrors the decisions in `reflected_color` and `refracted_color`.
+fn secondary_rays<'a>(world: &'a World, r: &Ray, remaining: u8) -> Vec<SecondaryRay<'a>> {
+    if remaining == 0 {
+        return vec![];
+    }
+
+    let xs = world.intersect(r);
+    let hit = match xs.locations.iter().find(|i| i.t > 0.) {
+        Some(hit) => hit,
+        None => return vec![],
+    };
+    let comps = prepare_computations(hit, r, &xs);
+    let material = &hit.object.material;
+
+    let mut rays = vec![];
+    if material.reflection_scale_at(hit.object, comps.point) > 0. {
+        rays.push((
+            SecondaryKind::Reflection,
+            ray(comps.over_point, comps.reflectv),
+        ));
+    }
+    if material.transparency_at(hit.object, comps.point) > 0. {
+        if let Some(direction) = refraction_direction(&comps, comps.n1, comps.n2) {
+            rays.push((SecondaryKind::Refraction, ray(comps.under_point, direction)));
+        }
+    }
+
+    return rays
+        .into_iter()
+        .map(|(kind, r)| SecondaryRay {
+            kind,
+            hit: picks_along(world, &r).into_iter().next(),
+            spawned: secondary_rays(world, &r, remaining - 1),
+            ray: r,
+        })
+        .collect();
+}
@@ -42,7 +42,7 @@ impl<'a> Intersect<'a> {
         self.locations.len()
     }
 
-    pub fn hit(&self) -> Option<Intersection> {
+    pub fn hit(&self) -> Option<Intersection<'a>> {
         return self
             .locations
             .clone()
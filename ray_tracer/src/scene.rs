@@ -0,0 +1,39 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{camera::Camera, world::World};
+
+// Everything needed to render an image, as loaded from a YAML scene file or
+// saved as JSON.
+#[derive(Serialize, Deserialize)]
+pub struct Scene {
+    pub world: World,
+    pub camera: Camera,
+}
+
+// Errors point at the offending line when one is known; problems with the
+// scene as a whole, like a missing camera, have none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneError {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+pub fn scene_error(message: &str) -> SceneError {
+    SceneError {
+        line: None,
+        message: message.to_string(),
+    }
+}
@@ -2,20 +2,22 @@ use std::fmt::Debug;
 
 use super::{plane::*, sphere::*};
 use crate::{
+    library::{glass, MaterialLibrary},
     material::{default_material, Material},
     matrix::{identity, Matrix},
     ray::{Intersect, Ray},
     tuple::{Tuple, TupleType},
 };
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Shape {
     Sphere(Sphere),
     Plane(Plane),
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Object {
     pub uuid: Uuid,
     pub shape: Shape,
@@ -28,6 +30,21 @@ impl Object {
         self.material = material;
     }
 
+    // Copies the named entry of `library` onto this object.
+    pub fn set_material_from(
+        &mut self,
+        library: &MaterialLibrary,
+        name: &str,
+    ) -> Result<(), String> {
+        match library.get(name) {
+            Some(material) => {
+                self.material = material;
+                Ok(())
+            }
+            None => Err(format!("unknown material '{}'", name)),
+        }
+    }
+
     pub fn set_transform(&mut self, transform: Matrix<f64>) {
         self.transform = transform;
     }
@@ -40,6 +57,10 @@ impl Object {
             Shape::Plane(shape) => shape.local_normal_at(object_point),
             // _ => panic!("Shape's local_normal_at has not been implemented"),
         };
+        let object_normal = match &self.material.bump {
+            Some(bump) => bump.perturb(object_point, object_normal),
+            None => object_normal,
+        };
         let mut world_normal = transform_inverse.transpose() * object_normal;
         world_normal.w = TupleType::Vector; // see pg. 82
         return world_normal.normalize();
@@ -71,8 +92,7 @@ impl Object {
     pub fn new_glass_sphere() -> Self {
         let s = default_sphere();
         let mut obj = Self::new(Shape::Sphere(s));
-        obj.material.transparency = 1.;
-        obj.material.refractive_index = 1.5;
+        obj.material = glass();
         return obj;
     }
 
@@ -3,10 +3,11 @@ use crate::{
     tuple::*,
     utils::EPSILON,
 };
+use serde::{Deserialize, Serialize};
 
 use super::object::Object;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Plane {}
 
 impl Plane {
@@ -2,10 +2,11 @@ use crate::{
     ray::{intersection, Intersect, Ray},
     tuple::*,
 };
+use serde::{Deserialize, Serialize};
 
 use super::object::Object;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sphere {
     pub center: Tuple,
     radius: f64,
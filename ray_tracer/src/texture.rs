@@ -0,0 +1,448 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    canvas::Canvas,
+    color::Color,
+    json::tagged,
+    matrix::{identity, InverseCache, Matrix},
+    pattern::Pattern,
+    scene::{scene_error, SceneError},
+    tuple::{vector, Tuple},
+};
+
+// A pattern defined over the unit square of texture coordinates rather than
+// over 3D pattern space. A `UvMapping` decides where a point lands on it.
+pub trait UvPattern: UvPatternClone + Send + Sync {
+    fn uv_pattern_at(&self, u: f64, v: f64) -> Color;
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        Err(scene_error("this UV pattern cannot be serialized"))
+    }
+
+    // Like `Pattern::problems`.
+    fn problems(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+// Lets boxed UV patterns be cloned, like `PatternClone`.
+pub trait UvPatternClone {
+    fn clone_uv_pattern(&self) -> Box<dyn UvPattern>;
+}
+
+impl<P: UvPattern + Clone + 'static> UvPatternClone for P {
+    fn clone_uv_pattern(&self) -> Box<dyn UvPattern> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn UvPattern> {
+    fn clone(&self) -> Self {
+        self.clone_uv_pattern()
+    }
+}
+
+impl fmt::Debug for dyn UvPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UvPattern")
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum UvMapping {
+    Spherical,
+    Planar,
+    Cylindrical,
+    Cube,
+}
+
+impl UvMapping {
+    pub fn map(&self, p: Tuple) -> (f64, f64) {
+        match self {
+            UvMapping::Spherical => spherical_map(p),
+            UvMapping::Planar => planar_map(p),
+            UvMapping::Cylindrical => cylindrical_map(p),
+            UvMapping::Cube => cube_uv(face_from_point(p), p),
+        }
+    }
+}
+
+pub fn spherical_map(p: Tuple) -> (f64, f64) {
+    let theta = p.x.atan2(p.z);
+    let radius = vector(p.x, p.y, p.z).magnitude();
+    let phi = (p.y / radius).acos();
+    let raw_u = theta / (2. * PI);
+
+    let u = 1. - (raw_u + 0.5);
+    let v = 1. - phi / PI;
+    return (u, v);
+}
+
+pub fn planar_map(p: Tuple) -> (f64, f64) {
+    return (p.x.rem_euclid(1.), p.z.rem_euclid(1.));
+}
+
+pub fn cylindrical_map(p: Tuple) -> (f64, f64) {
+    let theta = p.x.atan2(p.z);
+    let raw_u = theta / (2. * PI);
+
+    let u = 1. - (raw_u + 0.5);
+    let v = p.y.rem_euclid(1.);
+    return (u, v);
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CubeFace {
+    Left,
+    Right,
+    Front,
+    Back,
+    Up,
+    Down,
+}
+
+pub fn face_from_point(p: Tuple) -> CubeFace {
+    let coord = p.x.abs().max(p.y.abs()).max(p.z.abs());
+
+    return if coord == p.x {
+        CubeFace::Right
+    } else if coord == -p.x {
+        CubeFace::Left
+    } else if coord == p.y {
+        CubeFace::Up
+    } else if coord == -p.y {
+        CubeFace::Down
+    } else if coord == p.z {
+        CubeFace::Front
+    } else {
+        CubeFace::Back
+    };
+}
+
+// Maps a point on the given face of the [-1, 1] cube onto that face's own
+// unit square, as seen from outside the cube.
+pub fn cube_uv(face: CubeFace, p: Tuple) -> (f64, f64) {
+    let wrap = |a: f64| a.rem_euclid(2.) / 2.;
+
+    return match face {
+        CubeFace::Front => (wrap(p.x + 1.), wrap(p.y + 1.)),
+        CubeFace::Back => (wrap(1. - p.x), wrap(p.y + 1.)),
+        CubeFace::Left => (wrap(p.z + 1.), wrap(p.y + 1.)),
+        CubeFace::Right => (wrap(1. - p.z), wrap(p.y + 1.)),
+        CubeFace::Up => (wrap(p.x + 1.), wrap(1. - p.z)),
+        CubeFace::Down => (wrap(p.x + 1.), wrap(p.z + 1.)),
+    };
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UvCheckers {
+    pub width: f64,
+    pub height: f64,
+    pub a: Color,
+    pub b: Color,
+}
+
+impl UvPattern for UvCheckers {
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("checkers", self)
+    }
+
+    fn uv_pattern_at(&self, u: f64, v: f64) -> Color {
+        let u2 = (u * self.width).floor() as i64;
+        let v2 = (v * self.height).floor() as i64;
+
+        if (u2 + v2) % 2 == 0 {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
+
+// A test pattern for checking texture orientation: `main` fills the face
+// and each corner gets its own color.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UvAlignCheck {
+    pub main: Color,
+    pub ul: Color,
+    pub ur: Color,
+    pub bl: Color,
+    pub br: Color,
+}
+
+impl UvPattern for UvAlignCheck {
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("align-check", self)
+    }
+
+    fn uv_pattern_at(&self, u: f64, v: f64) -> Color {
+        if v > 0.8 {
+            if u < 0.2 {
+                return self.ul;
+            }
+            if u > 0.8 {
+                return self.ur;
+            }
+        } else if v < 0.2 {
+            if u < 0.2 {
+                return self.bl;
+            }
+            if u > 0.8 {
+                return self.br;
+            }
+        }
+
+        return self.main;
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+// What happens to texture coordinates outside of 0..1.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Wrap {
+    Repeat,
+    Clamp,
+}
+
+// A canvas sampled by UV, with (0, 0) at the bottom left of the image.
+// Textures loaded from a file remember its path and are saved as that path
+// rather than as their pixels.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ImageTexture {
+    pub canvas: Canvas,
+    pub filter: Filter,
+    pub wrap: Wrap,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+impl ImageTexture {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<ImageTexture> {
+        let texture = image_texture(Canvas::load(path.as_ref())?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        return Ok(ImageTexture {
+            path: Some(path.as_ref().to_path_buf()),
+            ..texture
+        });
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Color {
+        let (width, height) = (self.canvas.width() as i64, self.canvas.height() as i64);
+        let (x, y) = match self.wrap {
+            Wrap::Repeat => (x.rem_euclid(width), y.rem_euclid(height)),
+            Wrap::Clamp => (x.clamp(0, width - 1), y.clamp(0, height - 1)),
+        };
+
+        return self.canvas.pixel_at(x as usize, y as usize);
+    }
+}
+
+impl UvPattern for ImageTexture {
+    fn problems(&self) -> Vec<String> {
+        let (width, height) = (self.canvas.width(), self.canvas.height());
+        if width == 0 || height == 0 {
+            return vec![format!("image texture is empty ({}x{})", width, height)];
+        }
+        return vec![];
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        let mut json = tagged("image", self)?;
+        if self.path.is_some() {
+            if let Some(fields) = json.as_object_mut() {
+                fields.remove("canvas");
+            }
+        }
+        return Ok(json);
+    }
+
+    fn uv_pattern_at(&self, u: f64, v: f64) -> Color {
+        // texel centers sit at half-integer coordinates
+        let x = u * self.canvas.width() as f64 - 0.5;
+        let y = (1. - v) * self.canvas.height() as f64 - 0.5;
+
+        return match self.filter {
+            Filter::Nearest => self.texel(x.round() as i64, y.round() as i64),
+            Filter::Bilinear => {
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let top = self.texel(x0, y0) * (1. - fx) + self.texel(x0 + 1, y0) * fx;
+                let bottom = self.texel(x0, y0 + 1) * (1. - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+                top * (1. - fy) + bottom * fy
+            }
+        };
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextureMapPattern {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub mapping: UvMapping,
+    pub uv_pattern: Box<dyn UvPattern>,
+}
+
+impl Pattern for TextureMapPattern {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn problems(&self) -> Vec<String> {
+        self.uv_pattern.problems()
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("texture-map", self)
+    }
+
+    fn at_point(&self, point: Tuple) -> Color {
+        let (u, v) = self.mapping.map(point);
+        self.uv_pattern.uv_pattern_at(u, v)
+    }
+}
+
+// Wraps a cube with a separate UV pattern on each of its six faces.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CubeMapPattern {
+    pub transform: Matrix<f64>,
+    #[serde(skip)]
+    pub inverse: InverseCache,
+    pub left: Box<dyn UvPattern>,
+    pub front: Box<dyn UvPattern>,
+    pub right: Box<dyn UvPattern>,
+    pub back: Box<dyn UvPattern>,
+    pub up: Box<dyn UvPattern>,
+    pub down: Box<dyn UvPattern>,
+}
+
+impl Pattern for CubeMapPattern {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let faces = [
+            ("left", &self.left),
+            ("front", &self.front),
+            ("right", &self.right),
+            ("back", &self.back),
+            ("up", &self.up),
+            ("down", &self.down),
+        ];
+        return faces
+            .iter()
+            .flat_map(|(name, face)| {
+                face.problems()
+                    .into_iter()
+                    .map(move |problem| format!("{} face: {}", name, problem))
+            })
+            .collect();
+    }
+
+    fn to_json(&self) -> Result<Value, SceneError> {
+        tagged("cube-map", self)
+    }
+
+    fn at_point(&self, point: Tuple) -> Color {
+        let face = face_from_point(point);
+        let (u, v) = cube_uv(face, point);
+
+        let pattern = match face {
+            CubeFace::Left => &self.left,
+            CubeFace::Front => &self.front,
+            CubeFace::Right => &self.right,
+            CubeFace::Back => &self.back,
+            CubeFace::Up => &self.up,
+            CubeFace::Down => &self.down,
+        };
+        pattern.uv_pattern_at(u, v)
+    }
+}
+
+pub fn uv_checkers(width: f64, height: f64, a: Color, b: Color) -> UvCheckers {
+    UvCheckers {
+        width,
+        height,
+        a,
+        b,
+    }
+}
+
+pub fn uv_align_check(main: Color, ul: Color, ur: Color, bl: Color, br: Color) -> UvAlignCheck {
+    UvAlignCheck {
+        main,
+        ul,
+        ur,
+        bl,
+        br,
+    }
+}
+
+// Errors on an empty canvas, which has no texel to sample.
+pub fn image_texture(canvas: Canvas) -> Result<ImageTexture, String> {
+    if canvas.width() == 0 || canvas.height() == 0 {
+        return Err(format!(
+            "image texture is empty ({}x{})",
+            canvas.width(),
+            canvas.height()
+        ));
+    }
+
+    return Ok(ImageTexture {
+        canvas,
+        filter: Filter::Nearest,
+        wrap: Wrap::Repeat,
+        path: None,
+    });
+}
+
+pub fn texture_map(mapping: UvMapping, uv_pattern: Box<dyn UvPattern>) -> TextureMapPattern {
+    TextureMapPattern {
+        transform: identity(),
+        inverse: InverseCache::default(),
+        mapping,
+        uv_pattern,
+    }
+}
+
+pub fn cube_map(
+    left: Box<dyn UvPattern>,
+    front: Box<dyn UvPattern>,
+    right: Box<dyn UvPattern>,
+    back: Box<dyn UvPattern>,
+    up: Box<dyn UvPattern>,
+    down: Box<dyn UvPattern>,
+) -> CubeMapPattern {
+    CubeMapPattern {
+        transform: identity(),
+        inverse: InverseCache::default(),
+        left,
+        front,
+        right,
+        back,
+        up,
+        down,
+    }
+}
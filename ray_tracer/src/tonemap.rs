@@ -0,0 +1,66 @@
+use crate::color::{color, Color};
+
+// Maps the renderer's unbounded linear values into the 0..1 range a display
+// can show. A canvas applies them in order with `Canvas::tone_map`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ToneMap {
+    // Scales by 2^stops, so +1 doubles the brightness.
+    Exposure(f64),
+    Clamp,
+    // Compresses luminance by L / (1 + L), keeping the hue.
+    Reinhard,
+    // Krzysztof Narkowicz's fit of the ACES filmic curve.
+    AcesFilmic,
+}
+
+impl ToneMap {
+    pub fn map(&self, c: Color) -> Color {
+        return match self {
+            ToneMap::Exposure(stops) => c * 2f64.powf(*stops),
+            ToneMap::Clamp => color(clamp(c.r), clamp(c.g), clamp(c.b)),
+            ToneMap::Reinhard => {
+                let l = luminance(c);
+                if l <= 0. {
+                    color(0., 0., 0.)
+                } else {
+                    c * (1. / (1. + l))
+                }
+            }
+            ToneMap::AcesFilmic => color(aces(c.r), aces(c.g), aces(c.b)),
+        };
+    }
+}
+
+// Rec. 709 luminance of a linear color.
+pub fn luminance(c: Color) -> f64 {
+    0.2126 * c.r + 0.7152 * c.g + 0.0722 * c.b
+}
+
+fn clamp(v: f64) -> f64 {
+    v.clamp(0., 1.)
+}
+
+fn aces(x: f64) -> f64 {
+    let x = x.max(0.);
+    clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14))
+}
+
+// The sRGB transfer function, applied when linear values are stored in an
+// 8 or 16-bit image. Inputs are clamped to 0..1.
+pub fn srgb_encode(linear: f64) -> f64 {
+    let v = clamp(linear);
+    if v <= 0.0031308 {
+        12.92 * v
+    } else {
+        1.055 * v.powf(1. / 2.4) - 0.055
+    }
+}
+
+pub fn srgb_decode(encoded: f64) -> f64 {
+    let v = clamp(encoded);
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
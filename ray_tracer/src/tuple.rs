@@ -1,9 +1,10 @@
 use crate::matrix::Matrix;
 use crate::transforms::*;
 use crate::utils::*;
+use serde::{Deserialize, Serialize};
 use std::{fmt, ops};
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum TupleType {
     Point,
     Vector,
@@ -11,7 +12,7 @@ pub enum TupleType {
 
 type TT = TupleType;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct Tuple {
     pub x: Scalar,
     pub y: Scalar,
@@ -5,3 +5,12 @@ pub const RECURSION_DEPTH: u8 = 5;
 pub fn is_odd(n: usize) -> bool {
     return if n == 0 { false } else { n % 2 != 0 };
 }
+
+// Derives a new, well spread seed from `seed` and `n` (splitmix64), so every
+// pixel, sample and bounce gets its own reproducible random numbers.
+pub fn mix_seed(seed: u64, n: u64) -> u64 {
+    let mut z = seed.wrapping_add(n.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    return z ^ (z >> 31);
+}
@@ -1,21 +1,81 @@
 use crate::{
-    color::{color, Color},
+    color::{color, Channel, Color, CHANNELS},
+    diagnostics::{validate_world, Diagnostic},
     light::{is_shadowed, lighting, point_light, PointLight},
-    material::Material,
+    material::{Dispersion, Material, ShadingModel},
+    media::{Fog, Medium},
+    microfacet::{base_reflectance, fresnel_schlick, sample_ggx_half_vector, sample_lobe},
     ray::{ray, Intersect, Intersection, Ray},
     shapes::object::Object,
     transforms::scaling,
     tuple::{point, Tuple},
-    utils::EPSILON,
+    utils::{mix_seed, EPSILON},
 };
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct World {
     pub objects: Vec<Object>,
     pub light: PointLight,
+    pub fog: Option<Fog>,
+    pub media: Vec<Medium>,
+}
+
+// What a ray carries down the recursion besides its remaining depth.
+#[derive(Copy, Clone)]
+struct Path {
+    // Set once a dispersive surface has split the light into channels: the
+    // ray then only stands for this one.
+    channel: Option<Channel>,
+    // Glossy and microfacet sampling draw from an rng seeded with this, so
+    // the same pixel and sample always trace the same rays.
+    seed: u64,
+    // Whether the ray came from the camera rather than from a bounce.
+    primary: bool,
+}
+
+// Separate random streams for the two kinds of secondary ray at a hit.
+const REFLECTION: u64 = 0;
+const REFRACTION: u64 = 1;
+
+impl Path {
+    fn camera(seed: u64) -> Path {
+        Path {
+            channel: None,
+            seed,
+            primary: true,
+        }
+    }
+
+    // The path of the i-th secondary ray spawned where this one hit.
+    fn bounce(&self, i: usize) -> Path {
+        Path {
+            primary: false,
+            ..self.branch(i as u64)
+        }
+    }
+
+    fn branch(&self, n: u64) -> Path {
+        Path {
+            seed: mix_seed(self.seed, n),
+            ..*self
+        }
+    }
+
+    fn rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.seed)
+    }
 }
 
 impl World {
-    pub fn intersect(&self, r: &Ray) -> Intersect {
+    // Checks the world for mistakes that would otherwise only surface as a
+    // panic or a broken image partway through a render.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        validate_world(self)
+    }
+
+    pub fn intersect(&self, r: &Ray) -> Intersect<'_> {
         let mut locations = vec![];
         for obj in &self.objects {
             let mut i = r.intersect(obj);
@@ -28,12 +88,44 @@ impl World {
         return Intersect { locations };
     }
 
-    pub fn shade_hit<'a>(&self, c: &'a PreparedComputations, remaining: u8) -> Color {
+    pub fn shade_hit(&self, c: &PreparedComputations, remaining: u8) -> Color {
+        return self.shade(c, remaining, Path::camera(0));
+    }
+
+    pub fn color_at(&self, r: &Ray, remaining_depth: u8) -> Color {
+        return self.color_at_seeded(r, remaining_depth, 0);
+    }
+
+    // `color_at` with the seed for any random sampling along the way, e.g.
+    // one per pixel and sample so renders are reproducible.
+    pub fn color_at_seeded(&self, r: &Ray, remaining_depth: u8, seed: u64) -> Color {
+        return self.trace(r, remaining_depth, Path::camera(seed)).0;
+    }
+
+    // `color_at_seeded` that also returns the hit the color came from.
+    pub fn color_and_hit_seeded(
+        &self,
+        r: &Ray,
+        remaining_depth: u8,
+        seed: u64,
+    ) -> (Color, Option<Intersection<'_>>) {
+        return self.trace_hit(r, remaining_depth, Path::camera(seed));
+    }
+
+    pub fn reflected_color(&self, comps: &PreparedComputations, remaining: u8) -> Color {
+        return self.reflect(comps, remaining, Path::camera(0));
+    }
+
+    pub fn refracted_color(&self, comps: &PreparedComputations, remaining: u8) -> Color {
+        return self.refract(comps, remaining, Path::camera(0));
+    }
+
+    fn shade(&self, c: &PreparedComputations, remaining: u8, path: Path) -> Color {
         let shadowed = is_shadowed(self, c.over_point);
 
         let surface = lighting(
             &c.object.material,
-            &c.object,
+            c.object,
             &self.light,
             c.point,
             c.eyev,
@@ -41,65 +133,305 @@ impl World {
             shadowed,
         );
 
-        let reflected = self.reflected_color(c, remaining);
-        let refracted = self.refracted_color(c, remaining);
+        let reflected = self.reflect(c, remaining, path);
+        let refracted = self.refract(c, remaining, path);
 
         let material = &c.object.material;
+        if let ShadingModel::Microfacet { metallic, ior, .. } = material.model {
+            // `reflect` has already weighed the reflection by Fresnel; only
+            // the light that isn't reflected is left to refract
+            let reflectance = microfacet_fresnel(c, metallic, ior);
+            return surface + reflected + refracted * (color(1., 1., 1.) - reflectance);
+        }
 
-        if material.reflective > 0. && material.transparency > 0. {
-            let reflectance = schlick(c);
-            return surface + reflected * reflectance + refracted * (1.0 - reflectance);
+        let reflective = material.reflective_at(c.object, c.point);
+        let transparency = material.transparency_at(c.object, c.point);
+
+        if reflective > 0. && transparency > 0. {
+            let reflectance = match path.channel {
+                Some(channel) => {
+                    let (n1, n2) = c.refractive_indices(channel);
+                    let reflectance = schlick_with(c, n1, n2);
+                    color(reflectance, reflectance, reflectance)
+                }
+                None => schlick_color(c),
+            };
+            return surface
+                + reflected * reflectance
+                + refracted * (color(1., 1., 1.) - reflectance);
         } else {
             return surface + reflected + refracted;
         }
     }
 
-    pub fn color_at(&self, r: &Ray, remaining_depth: u8) -> Color {
+    // Like `color_at`, but also returns the `t` of the hit the color came
+    // from, or infinity when the ray escaped the scene.
+    fn trace(&self, r: &Ray, remaining_depth: u8, path: Path) -> (Color, f64) {
+        let (c, hit) = self.trace_hit(r, remaining_depth, path);
+        return (c, hit.map_or(f64::INFINITY, |i| i.t));
+    }
+
+    fn trace_hit(
+        &self,
+        r: &Ray,
+        remaining_depth: u8,
+        path: Path,
+    ) -> (Color, Option<Intersection<'_>>) {
         let intersections = self.intersect(r);
+        let hit = intersections.hit();
 
-        return match intersections.hit() {
+        let (surface, t) = match &hit {
             Some(i) => {
-                let comps = prepare_computations(&i, r, &intersections);
-                self.shade_hit(&comps, remaining_depth)
+                let comps = prepare_computations(i, r, &intersections);
+                (self.shade(&comps, remaining_depth, path), i.t)
             }
-            None => color(0., 0., 0.),
+            None => (color(0., 0., 0.), f64::INFINITY),
         };
+
+        return (self.apply_atmosphere(r, surface, t), hit);
     }
 
-    pub fn reflected_color(&self, comps: &PreparedComputations, remaining: u8) -> Color {
-        if remaining <= 0 || comps.object.material.reflective == 0. {
+    // Attenuates `c`, the color found `t` along `r`, by every medium and the
+    // fog lying between it and the ray origin.
+    pub fn apply_atmosphere(&self, r: &Ray, c: Color, t: f64) -> Color {
+        let mut media: Vec<(f64, &Medium)> = self
+            .media
+            .iter()
+            .filter_map(|m| m.segments(r, t).first().map(|(enter, _)| (*enter, m)))
+            .collect();
+        media.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+
+        let attenuated = media.iter().fold(c, |acc, (_, m)| m.march(self, r, acc, t));
+
+        return match &self.fog {
+            Some(fog) => fog.apply(attenuated, t * r.direction.magnitude()),
+            None => attenuated,
+        };
+    }
+
+    fn reflect(&self, comps: &PreparedComputations, remaining: u8, path: Path) -> Color {
+        let material = &comps.object.material;
+        if remaining == 0 {
             return color(0., 0., 0.);
         }
 
-        let reflected_ray = ray(comps.over_point, comps.reflectv);
-
-        let reflected_color = self.color_at(&reflected_ray, remaining - 1);
+        let reflective = material.reflection_scale_at(comps.object, comps.point);
+        if reflective == 0. {
+            return color(0., 0., 0.);
+        }
 
-        return reflected_color * comps.object.material.reflective;
+        let samples = glossy_samples(material, path);
+        let path = path.branch(REFLECTION);
+        let mut rng = path.rng();
+
+        return match material.model {
+            ShadingModel::Phong => {
+                let roughness = material.roughness_at(comps.object, comps.point);
+                let directions = jittered_directions(
+                    comps.reflectv,
+                    comps.normalv,
+                    roughness,
+                    samples,
+                    &mut rng,
+                );
+                let reflected_color = self.average(&directions, |i, direction| {
+                    let path = path.bounce(i);
+                    let (reflected, t) =
+                        self.trace(&ray(comps.over_point, direction), remaining - 1, path);
+                    absorbed(reflected, comps.n1_object, t, direction)
+                });
+
+                reflected_color * reflective
+            }
+            ShadingModel::Microfacet { metallic, ior, .. } => {
+                let roughness = material.roughness_at(comps.object, comps.point);
+                // Reflection rays go through randomly drawn microfacets, so
+                // rough surfaces turn glossy as more samples are averaged.
+                let directions = if roughness > 0. {
+                    (0..samples)
+                        .map(|_| {
+                            let halfv = sample_ggx_half_vector(comps.normalv, roughness, &mut rng);
+                            let candidate = (-comps.eyev).reflect(halfv);
+                            if candidate.dot(comps.normalv) > 0. {
+                                candidate
+                            } else {
+                                comps.reflectv
+                            }
+                        })
+                        .collect()
+                } else {
+                    vec![comps.reflectv]
+                };
+
+                let reflectance = microfacet_fresnel(comps, metallic, ior);
+
+                let reflected_color = self.average(&directions, |i, direction| {
+                    let path = path.bounce(i);
+                    let (reflected, t) =
+                        self.trace(&ray(comps.over_point, direction), remaining - 1, path);
+                    absorbed(reflected, comps.n1_object, t, direction)
+                });
+                reflected_color * reflectance * reflective
+            }
+        };
     }
 
-    pub fn refracted_color(&self, comps: &PreparedComputations, remaining: u8) -> Color {
-        if comps.object.material.transparency == 0. || remaining <= 0 {
+    fn refract(&self, comps: &PreparedComputations, remaining: u8, path: Path) -> Color {
+        let material = &comps.object.material;
+        if remaining == 0 || material.transparency_at(comps.object, comps.point) == 0. {
             return color(0., 0., 0.);
         }
 
-        let n_ratio = comps.n1 / comps.n2;
-        let cos_i = comps.eyev.dot(comps.normalv);
-        let sin2_t = n_ratio.powi(2) * (1. - cos_i.powi(2));
-        if sin2_t > 1. {
-            return color(0., 0., 0.);
+        if let Some(channel) = path.channel {
+            let (n1, n2) = comps.refractive_indices(channel);
+            return self.refracted_color_with(comps, n1, n2, remaining, path);
+        }
+        if !comps.is_dispersive() {
+            return self.refracted_color_with(comps, comps.n1, comps.n2, remaining, path);
         }
 
-        let cos_t = (1.0 - sin2_t).sqrt();
-        let direction = comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio;
+        // Each channel bends by its own index, so trace them separately and
+        // keep only the matching channel of each result. Rays below here
+        // stand for that one channel and don't split again.
+        let [r, g, b] = CHANNELS.map(|channel| {
+            let (n1, n2) = comps.refractive_indices(channel);
+            let path = Path {
+                channel: Some(channel),
+                ..path
+            };
+            self.refracted_color_with(comps, n1, n2, remaining, path)
+                .channel(channel)
+        });
+        return color(r, g, b);
+    }
+
+    fn refracted_color_with(
+        &self,
+        comps: &PreparedComputations,
+        n1: f64,
+        n2: f64,
+        remaining: u8,
+        path: Path,
+    ) -> Color {
+        let direction = match refraction_direction(comps, n1, n2) {
+            Some(direction) => direction,
+            None => return color(0., 0., 0.),
+        };
+
+        let material = &comps.object.material;
+        let samples = glossy_samples(material, path);
+        let roughness = material.roughness_at(comps.object, comps.point);
+        let path = path.branch(REFRACTION);
+        let directions = jittered_directions(
+            direction,
+            comps.normalv,
+            roughness,
+            samples,
+            &mut path.rng(),
+        );
+
+        let refracted = self.average(&directions, |i, direction| {
+            let path = path.bounce(i);
+            let (refracted, t) =
+                self.trace(&ray(comps.under_point, direction), remaining - 1, path);
+            absorbed(refracted, comps.n2_object, t, direction)
+        });
+
+        return refracted * material.transparency_at(comps.object, comps.point);
+    }
 
-        let refracted_ray = ray(comps.under_point, direction);
+    fn average<F>(&self, directions: &[Tuple], f: F) -> Color
+    where
+        F: Fn(usize, Tuple) -> Color,
+    {
+        let total = directions
+            .iter()
+            .enumerate()
+            .fold(color(0., 0., 0.), |acc, (i, direction)| {
+                acc + f(i, *direction)
+            });
+
+        return total * (1. / directions.len() as f64);
+    }
+}
+
+// How much of the light arriving along `eyev` a microfacet surface reflects.
+fn microfacet_fresnel(comps: &PreparedComputations, metallic: f64, ior: f64) -> Color {
+    let cos = comps.eyev.dot(comps.normalv);
+    let base_color = comps.object.material.color_at(comps.object, comps.point);
+    let f0 = base_reflectance(base_color, metallic, ior);
+    return fresnel_schlick(cos, f0);
+}
+
+// Beer–Lambert attenuation of `c`, found `t` along a secondary ray going
+// in `direction` from inside `through`. Reflected rays travel through the
+// object on the n1 side of the surface and refracted ones the n2 side.
+fn absorbed(c: Color, through: Option<&Object>, t: f64, direction: Tuple) -> Color {
+    return match through {
+        Some(object) if t.is_finite() => c * object.material.attenuation(t * direction.magnitude()),
+        _ => c,
+    };
+}
 
-        return self.color_at(&refracted_ray, remaining - 1) * comps.object.material.transparency;
+// The direction light bends into going from index `n1` to `n2`, or None on
+// total internal reflection.
+pub fn refraction_direction(comps: &PreparedComputations, n1: f64, n2: f64) -> Option<Tuple> {
+    let n_ratio = n1 / n2;
+    let cos_i = comps.eyev.dot(comps.normalv);
+    let sin2_t = n_ratio.powi(2) * (1. - cos_i.powi(2));
+    if sin2_t > 1. {
+        return None;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    return Some(comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio);
+}
+
+// Glossy surfaces average several secondary rays where camera rays hit
+// them; deeper bounces trace a single jittered ray to keep the ray count
+// bounded.
+fn glossy_samples(material: &Material, path: Path) -> usize {
+    if path.primary {
+        material.glossy_samples.max(1)
+    } else {
+        1
     }
 }
+
+// `samples` directions scattered around `direction` within a lobe whose
+// width grows with `roughness`. Directions that would cross to the other
+// side of the surface fall back to the perfect one.
+fn jittered_directions(
+    direction: Tuple,
+    normalv: Tuple,
+    roughness: f64,
+    samples: usize,
+    rng: &mut StdRng,
+) -> Vec<Tuple> {
+    if roughness <= 0. {
+        return vec![direction];
+    }
+
+    let side = direction.dot(normalv).signum();
+
+    return (0..samples)
+        .map(|_| {
+            let candidate = sample_lobe(direction.normalize(), roughness, rng);
+            if candidate.dot(normalv).signum() == side {
+                candidate
+            } else {
+                direction
+            }
+        })
+        .collect();
+}
 pub fn world(light: PointLight, objects: Vec<Object>) -> World {
-    World { light, objects }
+    World {
+        light,
+        objects,
+        fog: None,
+        media: vec![],
+    }
 }
 
 pub fn default_world() -> World {
@@ -118,6 +450,16 @@ pub fn default_world() -> World {
         reflective: 0.0,
         transparency: 0.,
         refractive_index: 1.,
+        absorption: color(0., 0., 0.),
+        dispersion: Dispersion::None,
+        model: ShadingModel::Phong,
+        roughness: 0.,
+        glossy_samples: 8,
+        reflective_pattern: None,
+        specular_pattern: None,
+        transparency_pattern: None,
+        roughness_pattern: None,
+        bump: None,
     });
     let mut sphere2 = Object::new_sphere();
     sphere2.set_transform(scaling(0.5, 0.5, 0.5));
@@ -136,13 +478,30 @@ pub struct PreparedComputations<'a> {
     pub reflectv: Tuple,
     pub n1: f64,
     pub n2: f64,
+    pub n1_object: Option<&'a Object>,
+    pub n2_object: Option<&'a Object>,
     pub under_point: Tuple,
 }
 
+impl<'a> PreparedComputations<'a> {
+    pub fn is_dispersive(&self) -> bool {
+        [self.n1_object, self.n2_object]
+            .iter()
+            .flatten()
+            .any(|o| o.material.is_dispersive())
+    }
+
+    pub fn refractive_indices(&self, channel: Channel) -> (f64, f64) {
+        let index =
+            |o: Option<&Object>| o.map_or(1.0, |o| o.material.refractive_index_for(channel));
+        (index(self.n1_object), index(self.n2_object))
+    }
+}
+
 pub fn prepare_computations<'a>(
     i: &'a Intersection,
     r: &Ray,
-    xs: &Intersect,
+    xs: &Intersect<'a>,
 ) -> PreparedComputations<'a> {
     let point = r.position(i.t);
     let mut normalv = i.object.normal_at(point);
@@ -158,17 +517,13 @@ pub fn prepare_computations<'a>(
 
     let reflectv = r.direction.reflect(normalv);
 
-    let mut containers: Vec<&Intersection> = vec![];
-    let mut n1 = 0.0;
-    let mut n2 = 0.0;
+    let mut containers: Vec<&Intersection<'a>> = vec![];
+    let mut n1_object = None;
+    let mut n2_object = None;
 
     for intersect in xs.locations.iter() {
         if i == intersect {
-            if containers.is_empty() {
-                n1 = 1.0
-            } else {
-                n1 = containers.last().unwrap().object.material.refractive_index
-            }
+            n1_object = containers.last().map(|c| c.object);
         }
 
         if let Some(index) = containers.iter().position(|value| *value == intersect) {
@@ -178,17 +533,16 @@ pub fn prepare_computations<'a>(
         }
 
         if i == intersect {
-            if containers.is_empty() {
-                n2 = 1.0
-            } else {
-                n2 = containers.last().unwrap().object.material.refractive_index
-            }
+            n2_object = containers.last().map(|c| c.object);
             break;
         }
     }
 
+    let index_of = |o: Option<&Object>| o.map_or(1.0, |o| o.material.refractive_index);
+    let (n1, n2) = (index_of(n1_object), index_of(n2_object));
+
     PreparedComputations {
-        object: &i.object,
+        object: i.object,
         t: i.t,
         point,
         eyev,
@@ -198,14 +552,35 @@ pub fn prepare_computations<'a>(
         reflectv,
         n1,
         n2,
+        n1_object,
+        n2_object,
         under_point,
     }
 }
 
 pub fn schlick(comps: &PreparedComputations) -> f64 {
+    return schlick_with(comps, comps.n1, comps.n2);
+}
+
+// Per-channel Schlick reflectance; identical in every channel unless one of
+// the materials on either side of the surface is dispersive.
+pub fn schlick_color(comps: &PreparedComputations) -> Color {
+    if !comps.is_dispersive() {
+        let reflectance = schlick(comps);
+        return color(reflectance, reflectance, reflectance);
+    }
+
+    let [r, g, b] = CHANNELS.map(|channel| {
+        let (n1, n2) = comps.refractive_indices(channel);
+        schlick_with(comps, n1, n2)
+    });
+    return color(r, g, b);
+}
+
+fn schlick_with(comps: &PreparedComputations, n1: f64, n2: f64) -> f64 {
     let mut cos = comps.eyev.dot(comps.normalv);
-    if comps.n1 > comps.n2 {
-        let n = comps.n1 / comps.n2;
+    if n1 > n2 {
+        let n = n1 / n2;
         let sin2_t = n.powi(2) * (1.0 - cos.powi(2));
         if sin2_t > 1.0 {
             return 1.0;
@@ -214,6 +589,6 @@ pub fn schlick(comps: &PreparedComputations) -> f64 {
         cos = cos_t;
     }
 
-    let r0 = ((comps.n1 - comps.n2) / (comps.n1 + comps.n2)).powi(2);
+    let r0 = ((n1 - n2) / (n1 + n2)).powi(2);
     return r0 + (1.0 - r0) * (1.0 - cos).powi(5);
 }
@@ -0,0 +1,575 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+
+use crate::{
+    camera::{camera, view_transform, Camera},
+    color::{color, Color},
+    library::{material_library, MaterialLibrary},
+    light::{point_light, PointLight},
+    material::{default_material, Material},
+    matrix::{identity, Matrix},
+    pattern::*,
+    scene::{scene_error, Scene, SceneError},
+    shapes::object::Object,
+    transforms::{rotation_x, rotation_y, rotation_z, scaling, shearing, translation},
+    tuple::{point, vector, Tuple},
+    world::world,
+};
+
+// Scene files follow the YAML layout used by The Ray Tracer Challenge: a
+// list of `add:` items for the camera, lights and shapes, plus `define:`
+// items naming materials and transforms for later items to reuse.
+pub fn load_scene<P: AsRef<Path>>(path: P) -> Result<Scene, SceneError> {
+    let source = fs::read_to_string(path.as_ref()).map_err(|e| SceneError {
+        line: None,
+        message: format!("cannot read {}: {}", path.as_ref().display(), e),
+    })?;
+    return parse_scene(&source);
+}
+
+pub fn parse_scene(source: &str) -> Result<Scene, SceneError> {
+    let root = match parse_document(source)? {
+        Some(root) => root,
+        None => return Err(scene_error("scene file is empty")),
+    };
+
+    let mut builder = SceneBuilder {
+        defines: HashMap::new(),
+        library: material_library(),
+        camera: None,
+        light: None,
+        objects: vec![],
+    };
+    for item in root.list()? {
+        builder.item(item)?;
+    }
+
+    let camera = builder
+        .camera
+        .ok_or_else(|| scene_error("scene has no camera"))?;
+    let light = builder
+        .light
+        .ok_or_else(|| scene_error("scene has no light"))?;
+
+    return Ok(Scene {
+        world: world(light, builder.objects),
+        camera,
+    });
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Scalar(String),
+    List(Vec<Node>),
+    Map(Vec<(Node, Node)>),
+}
+
+// A YAML value along with the line it started on.
+#[derive(Debug, Clone)]
+struct Node {
+    value: Value,
+    line: usize,
+}
+
+impl Node {
+    fn error<T>(&self, message: String) -> Result<T, SceneError> {
+        Err(SceneError {
+            line: Some(self.line),
+            message,
+        })
+    }
+
+    fn scalar(&self) -> Result<&str, SceneError> {
+        match &self.value {
+            Value::Scalar(s) => Ok(s),
+            _ => self.error("expected a single value".to_string()),
+        }
+    }
+
+    fn list(&self) -> Result<&[Node], SceneError> {
+        match &self.value {
+            Value::List(items) => Ok(items),
+            _ => self.error("expected a list".to_string()),
+        }
+    }
+
+    fn map(&self) -> Result<&[(Node, Node)], SceneError> {
+        match &self.value {
+            Value::Map(entries) => Ok(entries),
+            _ => self.error("expected a mapping".to_string()),
+        }
+    }
+
+    fn number(&self) -> Result<f64, SceneError> {
+        let s = self.scalar()?;
+        match s.trim().parse::<f64>() {
+            Ok(n) => Ok(n),
+            Err(_) => self.error(format!("expected a number, found '{}'", s)),
+        }
+    }
+
+    fn numbers(&self, count: usize) -> Result<Vec<f64>, SceneError> {
+        let items = self.list()?;
+        if items.len() != count {
+            return self.error(format!("expected {} numbers, found {}", count, items.len()));
+        }
+        return items.iter().map(|n| n.number()).collect();
+    }
+
+    fn color(&self) -> Result<Color, SceneError> {
+        let c = self.numbers(3)?;
+        return Ok(color(c[0], c[1], c[2]));
+    }
+
+    fn point(&self) -> Result<Tuple, SceneError> {
+        let p = self.numbers(3)?;
+        return Ok(point(p[0], p[1], p[2]));
+    }
+
+    fn vector(&self) -> Result<Tuple, SceneError> {
+        let v = self.numbers(3)?;
+        return Ok(vector(v[0], v[1], v[2]));
+    }
+
+    fn get(&self, key: &str) -> Result<Option<&Node>, SceneError> {
+        for (k, v) in self.map()? {
+            if k.scalar()? == key {
+                return Ok(Some(v));
+            }
+        }
+        return Ok(None);
+    }
+
+    fn require(&self, key: &str) -> Result<&Node, SceneError> {
+        match self.get(key)? {
+            Some(node) => Ok(node),
+            None => self.error(format!("missing '{}'", key)),
+        }
+    }
+}
+
+fn parse_document(source: &str) -> Result<Option<Node>, SceneError> {
+    let mut tree = TreeBuilder {
+        stack: vec![],
+        anchors: HashMap::new(),
+        root: None,
+    };
+    let mut parser = Parser::new(source.chars());
+    if let Err(e) = parser.load(&mut tree, false) {
+        // drop the position the scanner appends, it becomes our line number
+        let text = e.to_string();
+        let message = match text.rsplit_once(" at line ") {
+            Some((message, _)) => message.to_string(),
+            None => text,
+        };
+        return Err(SceneError {
+            line: Some(e.marker().line()),
+            message,
+        });
+    }
+
+    return Ok(tree.root);
+}
+
+enum Partial {
+    List(Vec<Node>, usize, usize),
+    Map(Vec<(Node, Node)>, Option<Node>, usize, usize),
+}
+
+// Turns the parser's event stream into a tree of `Node`s, remembering the
+// line each value came from.
+struct TreeBuilder {
+    stack: Vec<Partial>,
+    anchors: HashMap<usize, Node>,
+    root: Option<Node>,
+}
+
+impl TreeBuilder {
+    fn push(&mut self, node: Node, anchor: usize) {
+        if anchor > 0 {
+            self.anchors.insert(anchor, node.clone());
+        }
+
+        match self.stack.last_mut() {
+            None => self.root = Some(node),
+            Some(Partial::List(items, _, _)) => items.push(node),
+            Some(Partial::Map(entries, key, _, _)) => match key.take() {
+                Some(k) => entries.push((k, node)),
+                None => *key = Some(node),
+            },
+        }
+    }
+}
+
+impl MarkedEventReceiver for TreeBuilder {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        let line = mark.line();
+        match ev {
+            Event::Scalar(s, _, anchor, _) => self.push(
+                Node {
+                    value: Value::Scalar(s),
+                    line,
+                },
+                anchor,
+            ),
+            Event::Alias(anchor) => {
+                if let Some(node) = self.anchors.get(&anchor).cloned() {
+                    self.push(node, 0);
+                }
+            }
+            Event::SequenceStart(anchor) => self.stack.push(Partial::List(vec![], line, anchor)),
+            Event::MappingStart(anchor) => {
+                self.stack.push(Partial::Map(vec![], None, line, anchor))
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                let (value, line, anchor) = match self.stack.pop() {
+                    Some(Partial::List(items, line, anchor)) => (Value::List(items), line, anchor),
+                    Some(Partial::Map(entries, _, line, anchor)) => {
+                        (Value::Map(entries), line, anchor)
+                    }
+                    None => return,
+                };
+                self.push(Node { value, line }, anchor);
+            }
+            _ => {}
+        }
+    }
+}
+
+struct SceneBuilder {
+    defines: HashMap<String, Node>,
+    library: MaterialLibrary,
+    camera: Option<Camera>,
+    light: Option<PointLight>,
+    objects: Vec<Object>,
+}
+
+impl SceneBuilder {
+    fn item(&mut self, item: &Node) -> Result<(), SceneError> {
+        if let Some(name) = item.get("define")? {
+            return self.define(item, name);
+        }
+        if let Some(kind) = item.get("add")? {
+            return self.add(item, kind);
+        }
+        return item.error("expected an 'add' or 'define' item".to_string());
+    }
+
+    // Stores the definition with any `extend:` already merged in, so later
+    // lookups never have to chase parents.
+    fn define(&mut self, item: &Node, name: &Node) -> Result<(), SceneError> {
+        check_keys(item, &["define", "extend", "value"])?;
+        let value = item.require("value")?;
+
+        let value = match item.get("extend")? {
+            Some(parent) => {
+                let base = self.lookup(parent)?;
+                let mut entries = base.map()?.to_vec();
+                for (key, v) in value.map()? {
+                    entries.retain(|(k, _)| k.scalar().ok() != key.scalar().ok());
+                    entries.push((key.clone(), v.clone()));
+                }
+                Node {
+                    value: Value::Map(entries),
+                    line: value.line,
+                }
+            }
+            None => value.clone(),
+        };
+
+        self.defines.insert(name.scalar()?.to_string(), value);
+        return Ok(());
+    }
+
+    fn lookup(&self, name: &Node) -> Result<&Node, SceneError> {
+        let key = name.scalar()?;
+        match self.defines.get(key) {
+            Some(node) => Ok(node),
+            None => name.error(format!("'{}' has not been defined", key)),
+        }
+    }
+
+    // Like `lookup`, for a name met while expanding the names in `chain`.
+    // A define reached again from inside itself would expand forever.
+    fn lookup_within(&self, name: &Node, chain: &mut Vec<String>) -> Result<&Node, SceneError> {
+        let key = name.scalar()?;
+        if chain.iter().any(|n| n == key) {
+            return name.error(format!("'{}' is defined in terms of itself", key));
+        }
+        let node = self.lookup(name)?;
+        chain.push(key.to_string());
+        return Ok(node);
+    }
+
+    fn add(&mut self, item: &Node, kind: &Node) -> Result<(), SceneError> {
+        match kind.scalar()? {
+            "camera" => {
+                check_keys(
+                    item,
+                    &[
+                        "add",
+                        "width",
+                        "height",
+                        "field-of-view",
+                        "from",
+                        "to",
+                        "up",
+                    ],
+                )?;
+                if self.camera.is_some() {
+                    return kind.error("scene already has a camera".to_string());
+                }
+                let mut c = camera(
+                    dimension(item.require("width")?)?,
+                    dimension(item.require("height")?)?,
+                    item.require("field-of-view")?.number()?,
+                );
+                c.transform = view_transform(
+                    item.require("from")?.point()?,
+                    item.require("to")?.point()?,
+                    item.require("up")?.vector()?,
+                );
+                self.camera = Some(c);
+            }
+            "light" => {
+                check_keys(item, &["add", "at", "intensity"])?;
+                if self.light.is_some() {
+                    return kind.error("scene already has a light".to_string());
+                }
+                self.light = Some(point_light(
+                    item.require("at")?.point()?,
+                    item.require("intensity")?.color()?,
+                ));
+            }
+            "sphere" | "plane" => {
+                check_keys(item, &["add", "material", "transform"])?;
+                let mut object = match kind.scalar()? {
+                    "sphere" => Object::new_sphere(),
+                    _ => Object::new_plane(),
+                };
+                if let Some(material) = item.get("material")? {
+                    object.material = self.material(material)?;
+                }
+                if let Some(transform) = item.get("transform")? {
+                    object.transform = self.transform(transform)?;
+                }
+                self.objects.push(object);
+            }
+            other => return kind.error(format!("cannot add '{}'", other)),
+        }
+        return Ok(());
+    }
+
+    // A material is either a mapping of properties or a name, which is
+    // looked up among the defines first and the material library second.
+    fn material(&self, node: &Node) -> Result<Material, SceneError> {
+        return self.material_within(node, &mut vec![]);
+    }
+
+    fn material_within(
+        &self,
+        node: &Node,
+        chain: &mut Vec<String>,
+    ) -> Result<Material, SceneError> {
+        if let Value::Scalar(name) = &node.value {
+            if self.defines.contains_key(name) {
+                let define = self.lookup_within(node, chain)?;
+                return self.material_within(define, chain);
+            }
+            return match self.library.get(name) {
+                Some(material) => Ok(material),
+                None => node.error(format!("unknown material '{}'", name)),
+            };
+        }
+
+        let mut material = default_material();
+        for (key, value) in node.map()? {
+            match key.scalar()? {
+                "color" => material.color = value.color()?,
+                "ambient" => material.ambient = value.number()?,
+                "diffuse" => material.diffuse = value.number()?,
+                "specular" => material.specular = value.number()?,
+                "shininess" => material.shininess = value.number()?,
+                "reflective" => material.reflective = value.number()?,
+                "transparency" => material.transparency = value.number()?,
+                "refractive-index" => material.refractive_index = value.number()?,
+                "roughness" => material.roughness = value.number()?,
+                "absorption" => material.absorption = value.color()?,
+                "pattern" => material.pattern = Some(Arc::from(self.pattern(value)?)),
+                other => return key.error(format!("unknown material property '{}'", other)),
+            }
+        }
+        return Ok(material);
+    }
+
+    fn pattern(&self, node: &Node) -> Result<Box<dyn Pattern>, SceneError> {
+        check_keys(node, &["type", "colors", "transform"])?;
+        let kind = node.require("type")?;
+        let colors = node.require("colors")?;
+        let slots = colors
+            .list()?
+            .iter()
+            .map(|slot| self.pattern_slot(slot))
+            .collect::<Result<Vec<_>, _>>()?;
+        let transform = match node.get("transform")? {
+            Some(t) => self.transform(t)?,
+            None => identity(),
+        };
+
+        let expected = if kind.scalar()? == "hexagons" { 3 } else { 2 };
+        if slots.len() != expected {
+            return colors.error(format!(
+                "'{}' takes {} colors, found {}",
+                kind.scalar()?,
+                expected,
+                slots.len()
+            ));
+        }
+        let mut slots = slots.into_iter();
+        let mut next = || slots.next().unwrap();
+
+        let pattern: Box<dyn Pattern> = match kind.scalar()? {
+            "stripes" => Box::new(StripePattern {
+                transform,
+                ..stripe_pattern(next(), next())
+            }),
+            "gradient" => Box::new(GradientPattern {
+                transform,
+                ..gradient_pattern(next(), next())
+            }),
+            "rings" => Box::new(RingPattern {
+                transform,
+                ..ring_pattern(next(), next())
+            }),
+            "checkers" => Box::new(CheckerPattern {
+                transform,
+                ..checker_pattern(next(), next())
+            }),
+            "radial-gradient" => Box::new(RadialGradientPattern {
+                transform,
+                ..radial_gradient_pattern(next(), next())
+            }),
+            "ring-gradient" => Box::new(RingGradientPattern {
+                transform,
+                ..ring_gradient_pattern(next(), next())
+            }),
+            "polka-dots" => Box::new(PolkaDotPattern {
+                transform,
+                ..polka_dot_pattern(next(), next())
+            }),
+            "blended" => Box::new(BlendedPattern {
+                transform,
+                ..blended_pattern(next(), next())
+            }),
+            "hexagons" => Box::new(HexPattern {
+                transform,
+                ..hex_pattern(next(), next(), next())
+            }),
+            other => return kind.error(format!("unknown pattern type '{}'", other)),
+        };
+        return Ok(pattern);
+    }
+
+    // Each entry of a pattern's `colors` is a color or a nested pattern.
+    fn pattern_slot(&self, node: &Node) -> Result<Box<dyn Pattern>, SceneError> {
+        match &node.value {
+            Value::Map(_) => self.pattern(node),
+            _ => Ok(node.color()?.into_pattern()),
+        }
+    }
+
+    // Transform lists apply in order, so the first entry is applied first
+    // and ends up rightmost in the product. Named entries expand in place.
+    fn transform(&self, node: &Node) -> Result<Matrix<f64>, SceneError> {
+        return self.transform_within(node, &mut vec![]);
+    }
+
+    fn transform_within(
+        &self,
+        node: &Node,
+        chain: &mut Vec<String>,
+    ) -> Result<Matrix<f64>, SceneError> {
+        let mut transform = identity();
+        for step in node.list()? {
+            let m = match &step.value {
+                Value::Scalar(_) => {
+                    let define = self.lookup_within(step, chain)?;
+                    let m = self.transform_within(define, chain)?;
+                    chain.pop();
+                    m
+                }
+                _ => transform_step(step)?,
+            };
+            transform = m * transform;
+        }
+        return Ok(transform);
+    }
+}
+
+fn transform_step(step: &Node) -> Result<Matrix<f64>, SceneError> {
+    let items = step.list()?;
+    let op = match items.first() {
+        Some(op) => op,
+        None => return step.error("empty transform".to_string()),
+    };
+    let args = items[1..]
+        .iter()
+        .map(|n| n.number())
+        .collect::<Result<Vec<f64>, _>>()?;
+
+    let arity = match op.scalar()? {
+        "translate" | "scale" => 3,
+        "rotate-x" | "rotate-y" | "rotate-z" => 1,
+        "shear" => 6,
+        other => return op.error(format!("unknown transform '{}'", other)),
+    };
+    if args.len() != arity {
+        return step.error(format!(
+            "'{}' takes {} numbers, found {}",
+            op.scalar()?,
+            arity,
+            args.len()
+        ));
+    }
+
+    let m = match op.scalar()? {
+        "translate" => translation(args[0], args[1], args[2]),
+        "scale" => scaling(args[0], args[1], args[2]),
+        "rotate-x" => rotation_x(args[0]),
+        "rotate-y" => rotation_y(args[0]),
+        "rotate-z" => rotation_z(args[0]),
+        _ => shearing(args[0], args[1], args[2], args[3], args[4], args[5]),
+    };
+    // Objects and patterns invert their transforms, so a flattening one
+    // would only fail later, in the middle of a render.
+    if m.inverse().is_none() {
+        return step.error(format!(
+            "'{}' flattens space and cannot be undone",
+            op.scalar()?
+        ));
+    }
+    return Ok(m);
+}
+
+fn dimension(node: &Node) -> Result<i16, SceneError> {
+    let n = node.number()?;
+    if n < 1. || n > i16::MAX as f64 || n.fract() != 0. {
+        return node.error(format!("expected a whole number of pixels, found {}", n));
+    }
+    return Ok(n as i16);
+}
+
+fn check_keys(node: &Node, allowed: &[&str]) -> Result<(), SceneError> {
+    for (key, _) in node.map()? {
+        let name = key.scalar()?;
+        if !allowed.contains(&name) {
+            return key.error(format!("unknown key '{}'", name));
+        }
+    }
+    return Ok(());
+}
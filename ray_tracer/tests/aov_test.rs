@@ -0,0 +1,89 @@
+use std::f64::consts::PI;
+
+use ray_tracer::{
+    aov::render_with_aovs,
+    camera::{camera, render, view_transform, Camera},
+    color::color,
+    tuple::{point, vector},
+    world::default_world,
+};
+
+fn camera_on_default_world() -> Camera {
+    let mut c = camera(11, 11, PI / 2.);
+    c.transform = view_transform(point(0., 0., -5.), point(0., 0., 0.), vector(0., 1., 0.));
+    return c;
+}
+
+#[test]
+fn beauty_image_matches_render() {
+    let (image, _) = render_with_aovs(camera_on_default_world(), default_world());
+    let expected = render(camera_on_default_world(), default_world());
+    assert_eq!(image.pixel_at(5, 5), expected.pixel_at(5, 5));
+    assert_eq!(image.pixel_at(5, 5), color(0.38066, 0.47583, 0.2855));
+}
+
+#[test]
+fn aovs_describe_the_first_hit() {
+    let w = default_world();
+    let outer = w.objects[0].uuid;
+    let (_, aovs) = render_with_aovs(camera_on_default_world(), w);
+
+    assert_eq!(aovs.depth.pixel_at(5, 5), color(4., 4., 4.));
+    assert_eq!(aovs.normal.pixel_at(5, 5), color(0., 0., -1.));
+    assert_eq!(aovs.albedo.pixel_at(5, 5), color(0.8, 1.0, 0.6));
+    assert_eq!(aovs.object_at(5, 5), Some(0));
+    assert_eq!(aovs.uuid_at(5, 5), Some(outer));
+}
+
+#[test]
+fn aovs_are_empty_where_rays_escape() {
+    let (_, aovs) = render_with_aovs(camera_on_default_world(), default_world());
+    assert_eq!(aovs.depth.pixel_at(0, 0), color(0., 0., 0.));
+    assert_eq!(aovs.normal.pixel_at(0, 0), color(0., 0., 0.));
+    assert_eq!(aovs.albedo.pixel_at(0, 0), color(0., 0., 0.));
+    assert_eq!(aovs.object_at(0, 0), None);
+    assert_eq!(aovs.uuid_at(0, 0), None);
+}
+
+#[test]
+fn masks_select_one_object() {
+    let w = default_world();
+    let (outer, inner) = (w.objects[0].uuid, w.objects[1].uuid);
+    let (_, aovs) = render_with_aovs(camera_on_default_world(), w);
+
+    assert_eq!(aovs.mask(outer).pixel_at(5, 5), color(1., 1., 1.));
+    assert_eq!(aovs.mask(outer).pixel_at(0, 0), color(0., 0., 0.));
+    assert_eq!(aovs.mask(inner).pixel_at(5, 5), color(0., 0., 0.));
+}
+
+#[test]
+fn object_index_leaves_zero_for_the_background() {
+    let (_, aovs) = render_with_aovs(camera_on_default_world(), default_world());
+    let index = aovs.object_index();
+    assert_eq!(index.pixel_at(5, 5), color(1., 1., 1.));
+    assert_eq!(index.pixel_at(0, 0), color(0., 0., 0.));
+}
+
+#[test]
+fn viewable_aovs_stay_in_unit_range() {
+    let (_, aovs) = render_with_aovs(camera_on_default_world(), default_world());
+    let depth = aovs.normalized_depth();
+    assert_eq!(depth.pixels.iter().cloned().fold(0., f64::max), 1.);
+    assert_eq!(depth.pixel_at(0, 0), color(0., 0., 0.));
+    assert_eq!(aovs.normal_colors().pixel_at(5, 5), color(0.5, 0.5, 0.));
+    assert_eq!(aovs.normal_colors().pixel_at(0, 0), color(0., 0., 0.));
+    for v in aovs.object_colors().pixels {
+        assert!((0. ..=1.).contains(&v));
+    }
+}
+
+#[test]
+fn supersampled_aovs_still_describe_the_middle_of_each_pixel() {
+    let mut c = camera_on_default_world();
+    c.samples = 4;
+    let (_, aovs) = render_with_aovs(c, default_world());
+
+    assert_eq!(aovs.depth.pixel_at(5, 5), color(4., 4., 4.));
+    assert_eq!(aovs.object_at(5, 5), Some(0));
+    assert_eq!(aovs.object_at(0, 0), None);
+}
@@ -0,0 +1,119 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use ray_tracer::{
+    bump::*,
+    color::color,
+    shapes::object::Object,
+    texture::{uv_checkers, UvMapping},
+    transforms::translation,
+    tuple::{point, vector},
+};
+
+#[test]
+fn flat_ripples_leave_the_normal_alone() {
+    let bump = ripple_bump(0., 2.);
+    let n = bump.perturb(point(1., 0., 0.), vector(0., 1., 0.));
+
+    assert_eq!(n, vector(0., 1., 0.));
+}
+
+#[test]
+fn ripples_tilt_the_normal_away_from_their_slope() {
+    let bump = ripple_bump(0.1, 2.);
+    let up = vector(0., 1., 0.);
+
+    // crest and trough: the surface is level
+    assert_eq!(bump.perturb(point(0.5, 0., 0.), up), up);
+    assert_eq!(bump.perturb(point(0., 0., -1.5), up), up);
+    // falling slope at r = 1
+    assert_eq!(
+        bump.perturb(point(1., 0., 0.), up),
+        vector(0.1 * PI, 1., 0.).normalize()
+    );
+}
+
+#[test]
+fn bump_transform_moves_the_ripples() {
+    let mut bump = ripple_bump(0.1, 2.);
+    bump.transform = translation(5., 0., 0.);
+    let up = vector(0., 1., 0.);
+
+    assert_eq!(
+        bump.perturb(point(6., 0., 0.), up),
+        vector(0.1 * PI, 1., 0.).normalize()
+    );
+}
+
+#[test]
+fn noise_bumps_tilt_the_normal_but_keep_it_unit_length() {
+    let bump = noise_bump(0.3);
+    let up = vector(0., 1., 0.);
+    let n = bump.perturb(point(0.37, 0., 1.21), up);
+
+    assert!((n.magnitude() - 1.).abs() < 0.00001);
+    assert!(n.dot(up) > 0.);
+    assert_ne!(n, up);
+    assert_eq!(noise_bump(0.).perturb(point(0.37, 0., 1.21), up), up);
+}
+
+#[test]
+fn normal_at_applies_the_material_bump() {
+    let mut plane = Object::new_plane();
+    plane.material.bump = Some(Arc::new(ripple_bump(0.1, 2.)));
+
+    assert_eq!(
+        plane.normal_at(point(1., 0., 0.)),
+        vector(0.1 * PI, 1., 0.).normalize()
+    );
+    assert_eq!(
+        Object::new_plane().normal_at(point(1., 0., 0.)),
+        vector(0., 1., 0.)
+    );
+}
+
+#[test]
+fn flat_normal_map_keeps_the_surface_normal() {
+    let flat = color(0.5, 0.5, 1.);
+    let map = image_normal_map(UvMapping::Planar, Box::new(uv_checkers(1., 1., flat, flat)));
+
+    assert_eq!(
+        map.perturb(point(0.3, 0., 0.6), vector(0., 1., 0.)),
+        vector(0., 1., 0.)
+    );
+}
+
+#[test]
+fn planar_normal_map_tilts_towards_u_and_v() {
+    let towards_u = color(1., 0.5, 1.);
+    let towards_v = color(0.5, 1., 1.);
+    let up = vector(0., 1., 0.);
+    let p = point(0.3, 0., 0.6);
+
+    let map = image_normal_map(
+        UvMapping::Planar,
+        Box::new(uv_checkers(1., 1., towards_u, towards_u)),
+    );
+    assert_eq!(map.perturb(p, up), vector(1., 1., 0.).normalize());
+
+    let map = image_normal_map(
+        UvMapping::Planar,
+        Box::new(uv_checkers(1., 1., towards_v, towards_v)),
+    );
+    assert_eq!(map.perturb(p, up), vector(0., 1., 1.).normalize());
+}
+
+#[test]
+fn spherical_normal_map_follows_the_surface() {
+    let towards_v = color(0.5, 1., 1.);
+    let map = image_normal_map(
+        UvMapping::Spherical,
+        Box::new(uv_checkers(1., 1., towards_v, towards_v)),
+    );
+
+    // +v points to the north pole
+    assert_eq!(
+        map.perturb(point(0., 0., 1.), vector(0., 0., 1.)),
+        vector(0., 1., 1.).normalize()
+    );
+}
@@ -2,7 +2,7 @@ use std::f64::consts::PI;
 
 use ray_tracer::{
     camera::{camera, render, view_transform},
-    color::color,
+    color::{black, color},
     matrix::{identity, Matrix},
     transforms::{rotation_y, scaling, translation},
     tuple::{point, vector},
@@ -121,3 +121,32 @@ fn rendering_a_world_with_a_camera() {
 
     assert_eq!(image.pixel_at(5, 5), color(0.38066, 0.47583, 0.2855))
 }
+
+#[test]
+fn constructing_ray_through_a_point_within_a_pixel() {
+    let c = camera(201, 101, PI / 2.);
+
+    assert_eq!(
+        c.ray_through(100, 50, 0.5, 0.5).direction,
+        vector(0., 0., -1.)
+    );
+    assert_eq!(
+        c.ray_through(0, 0, 0., 0.).direction,
+        vector(1., 101. / 201., -1.).normalize()
+    );
+}
+
+#[test]
+fn supersampling_averages_rays_across_the_pixel() {
+    let w = default_world();
+    let mut c = camera(1, 1, PI / 8.);
+    c.transform = view_transform(point(0., 0., -5.), point(0., 0., 0.), vector(0., 1., 0.));
+    let center = c.color_for_pixel(&w, 0, 0);
+
+    // the corners of this one big pixel miss the spheres
+    c.samples = 16;
+    let averaged = c.color_for_pixel(&w, 0, 0);
+
+    assert!(averaged.g > 0. && averaged.g < center.g);
+    assert_ne!(center, black());
+}
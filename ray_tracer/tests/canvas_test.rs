@@ -1,5 +1,6 @@
 use ray_tracer::canvas::*;
 use ray_tracer::color::*;
+use ray_tracer::tonemap::srgb_decode;
 
 #[test]
 fn test_canvas() {
@@ -74,3 +75,382 @@ fn test_long_lines_in_ppm() {
 
     assert_eq!(c.canvas_to_ppm(), expected_output);
 }
+
+#[test]
+fn reading_a_p3_ppm_with_comments_and_maxval() {
+    let ppm = "P3\n# a comment\n2 1 # trailing comment\n100\n100 0 50\n0 25 100\n";
+    let c = read::canvas_from_ppm(ppm.as_bytes()).unwrap();
+
+    assert_eq!(c.width(), 2);
+    assert_eq!(c.height(), 1);
+    assert_eq!(c.pixel_at(0, 0), color(1., 0., 0.5));
+    assert_eq!(c.pixel_at(1, 0), color(0., 0.25, 1.));
+}
+
+#[test]
+fn reading_a_binary_p6_ppm() {
+    let mut ppm = b"P6\n2 1\n255\n".to_vec();
+    ppm.extend_from_slice(&[255, 0, 51, 0, 102, 255]);
+    let c = read::canvas_from_ppm(&ppm).unwrap();
+
+    // unlike P3, the samples are sRGB encoded
+    assert_eq!(c.pixel_at(0, 0), color(1., 0., srgb_decode(0.2)));
+    assert_eq!(c.pixel_at(1, 0), color(0., srgb_decode(0.4), 1.));
+}
+
+#[test]
+fn reading_a_sixteen_bit_p6_ppm() {
+    let mut ppm = b"P6 1 1 65535\n".to_vec();
+    ppm.extend_from_slice(&[0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+    let c = read::canvas_from_ppm(&ppm).unwrap();
+
+    assert_eq!(
+        c.pixel_at(0, 0),
+        color(1., srgb_decode(32768. / 65535.), 0.)
+    );
+}
+
+#[test]
+fn reading_invalid_ppm_data_fails() {
+    assert!(read::canvas_from_ppm(b"P1\n1 1\n1\n").is_err());
+    assert!(read::canvas_from_ppm(b"P3\n2 2\n255\n0 0 0\n").is_err());
+    assert!(read::canvas_from_ppm(b"P6\n2 2\n255\n\x00\x00").is_err());
+}
+
+#[test]
+fn reading_a_ppm_with_samples_above_maxval_fails() {
+    assert!(read::canvas_from_ppm(b"P3\n1 1\n100\n0 101 0\n").is_err());
+    assert!(read::canvas_from_ppm(b"P6\n1 1\n100\n\x00\x65\x00").is_err());
+}
+
+#[test]
+fn reading_a_ppm_header_larger_than_the_file_fails() {
+    assert!(read::canvas_from_ppm(b"P6\n100000 100000\n255\n\x00\x00\x00").is_err());
+    assert!(read::canvas_from_ppm(b"P3\n100000 100000\n255\n0 0 0\n").is_err());
+    let overflowing = format!("P6\n{} {}\n255\n", usize::MAX, usize::MAX);
+    assert!(read::canvas_from_ppm(overflowing.as_bytes()).is_err());
+}
+
+#[test]
+fn loading_a_png_file() {
+    let path = std::env::temp_dir().join("ray_tracer_canvas_load_test.png");
+    {
+        let file = std::fs::File::create(&path).unwrap();
+        let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), 2, 1);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[255, 0, 0, 0, 51, 255]).unwrap();
+    }
+
+    let c = Canvas::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(c.width(), 2);
+    assert_eq!(c.pixel_at(0, 0), color(1., 0., 0.));
+    assert_eq!(c.pixel_at(1, 0), color(0., srgb_decode(0.2), 1.));
+}
+
+#[test]
+fn image_type_is_inferred_from_the_extension() {
+    assert_eq!(
+        ImageType::from_path("out/a.ppm").unwrap(),
+        ImageType::PPMBinary
+    );
+    assert_eq!(ImageType::from_path("A.PNG").unwrap(), ImageType::PNG);
+
+    let error = ImageType::from_path("a.gif").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(ImageType::from_path("no_extension").is_err());
+}
+
+#[test]
+fn saving_creates_missing_directories() {
+    let dir = std::env::temp_dir().join("ray_tracer_canvas_save_test");
+    let path = dir.join("nested/image.ppm");
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut c = canvas(2, 1);
+    c.write_pixel(1, 0, color(1., 0.2, 0.)).unwrap();
+
+    c.save(&path).unwrap();
+    let saved = std::fs::read(&path).unwrap();
+    let loaded = Canvas::load(&path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(saved, c.canvas_to_ppm_binary());
+    assert_eq!(loaded.pixel_at(1, 0).r, 1.);
+    assert!((loaded.pixel_at(1, 0).g - 0.2).abs() <= SRGB_STEP / 255.);
+}
+
+#[test]
+fn saving_to_an_unknown_format_fails() {
+    let path = std::env::temp_dir().join("ray_tracer_canvas_save_test.gif");
+
+    assert!(canvas(1, 1).save(&path).is_err());
+    assert!(!path.exists());
+}
+
+fn gradient_canvas() -> Canvas {
+    let mut c = canvas(3, 2);
+    c.write_pixel(0, 0, color(1., 0., 0.)).unwrap();
+    c.write_pixel(1, 0, color(0., 0.2, 1.)).unwrap();
+    c.write_pixel(2, 1, color(0.3, 1.5, -0.5)).unwrap();
+    c.write_pixel(0, 1, color(0.123456, 0.5, 0.999)).unwrap();
+    return c;
+}
+
+// Half an sRGB encoded step is at most this many linear steps, near white.
+const SRGB_STEP: f64 = 1.2;
+
+fn png_info(data: &[u8]) -> (png::BitDepth, png::ColorType) {
+    let reader = png::Decoder::new(data).read_info().unwrap();
+    let info = reader.info();
+    return (info.bit_depth, info.color_type);
+}
+
+#[test]
+fn eight_bit_png_round_trips() {
+    let c = gradient_canvas();
+    let data = c.canvas_to_png(png::BitDepth::Eight).unwrap();
+    let decoded = read::canvas_from_png(&data).unwrap();
+
+    assert_eq!(png_info(&data), (png::BitDepth::Eight, png::ColorType::Rgb));
+    assert_eq!((decoded.width(), decoded.height()), (3, 2));
+    // out of range values are clamped
+    assert_eq!(decoded.pixel_at(2, 1).g, 1.);
+    assert_eq!(decoded.pixel_at(2, 1).b, 0.);
+    for (a, b) in decoded.pixels.iter().zip(&c.pixels) {
+        assert!((a - b.clamp(0., 1.)).abs() <= SRGB_STEP / 255.);
+    }
+}
+
+#[test]
+fn sixteen_bit_png_keeps_more_precision() {
+    let c = gradient_canvas();
+    let data = c.canvas_to_png(png::BitDepth::Sixteen).unwrap();
+    let decoded = read::canvas_from_png(&data).unwrap();
+
+    assert_eq!(
+        png_info(&data),
+        (png::BitDepth::Sixteen, png::ColorType::Rgb)
+    );
+    for (a, b) in decoded.pixels.iter().zip(&c.pixels) {
+        assert!((a - b.clamp(0., 1.)).abs() <= SRGB_STEP / 65535.);
+    }
+}
+
+#[test]
+fn other_png_bit_depths_are_rejected() {
+    assert!(canvas(1, 1).canvas_to_png(png::BitDepth::Four).is_err());
+}
+
+#[test]
+fn saving_png_files() {
+    let dir = std::env::temp_dir().join("ray_tracer_canvas_png_test");
+    let c = gradient_canvas();
+
+    c.save(dir.join("eight.png")).unwrap();
+    c.save_as(dir.join("sixteen.png"), ImageType::PNG16)
+        .unwrap();
+    let eight = std::fs::read(dir.join("eight.png")).unwrap();
+    let sixteen = std::fs::read(dir.join("sixteen.png")).unwrap();
+    let loaded = Canvas::load(dir.join("sixteen.png")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(png_info(&eight).0, png::BitDepth::Eight);
+    assert_eq!(png_info(&sixteen).0, png::BitDepth::Sixteen);
+    assert_eq!(loaded.pixel_at(0, 0), color(1., 0., 0.));
+}
+
+#[test]
+fn binary_ppm_has_a_p6_header_and_one_byte_per_channel() {
+    let mut c = canvas(2, 1);
+    c.write_pixel(0, 0, color(1.5, 0.5, 0.)).unwrap();
+    c.write_pixel(1, 0, color(0., 0.2, -1.)).unwrap();
+
+    let mut expected = b"P6\n2 1\n255\n".to_vec();
+    expected.extend_from_slice(&[255, 188, 0, 0, 124, 0]);
+    assert_eq!(c.canvas_to_ppm_binary(), expected);
+}
+
+#[test]
+fn binary_ppm_round_trips_through_the_reader() {
+    let c = gradient_canvas();
+    let decoded = read::canvas_from_ppm(&c.canvas_to_ppm_binary()).unwrap();
+
+    assert_eq!((decoded.width(), decoded.height()), (3, 2));
+    for (a, b) in decoded.pixels.iter().zip(&c.pixels) {
+        assert!((a - b.clamp(0., 1.)).abs() <= SRGB_STEP / 255.);
+    }
+}
+
+#[test]
+fn writing_streams_the_same_bytes_as_the_string_encoders() {
+    let c = gradient_canvas();
+    let mut p3 = vec![];
+    let mut p6 = vec![];
+    c.write(&mut p3, ImageType::PPM).unwrap();
+    c.write(&mut p6, ImageType::PPMBinary).unwrap();
+
+    assert_eq!(p3, c.canvas_to_ppm().into_bytes());
+    assert_eq!(p6, c.canvas_to_ppm_binary());
+}
+
+#[test]
+fn saving_binary_ppm_files() {
+    let path = std::env::temp_dir().join("ray_tracer_canvas_p6_test.ppm");
+    let c = gradient_canvas();
+
+    c.save_as(&path, ImageType::PPMBinary).unwrap();
+    let saved = std::fs::read(&path).unwrap();
+    let loaded = Canvas::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(saved, c.canvas_to_ppm_binary());
+    assert!((loaded.pixel_at(1, 0).g - 0.2).abs() <= SRGB_STEP / 255.);
+}
+
+fn bright_canvas() -> Canvas {
+    let mut c = canvas(2, 2);
+    c.write_pixel(0, 0, color(12.5, 0.25, 0.)).unwrap();
+    c.write_pixel(1, 0, color(1., 2., 3.)).unwrap();
+    c.write_pixel(0, 1, color(0.001, 0.5, 1000.)).unwrap();
+    return c;
+}
+
+fn split_header(data: &[u8], lines: usize) -> (String, &[u8]) {
+    let mut end = 0;
+    for _ in 0..lines {
+        end += data[end..].iter().position(|b| *b == b'\n').unwrap() + 1;
+    }
+    return (
+        String::from_utf8_lossy(&data[..end]).to_string(),
+        &data[end..],
+    );
+}
+
+#[test]
+fn pfm_keeps_values_above_one() {
+    let c = bright_canvas();
+    let mut data = vec![];
+    c.write(&mut data, ImageType::PFM).unwrap();
+    let (header, raster) = split_header(&data, 3);
+
+    assert_eq!(header, "PF\n2 2\n-1.0\n");
+    assert_eq!(raster.len(), 2 * 2 * 3 * 4);
+    let values: Vec<f32> = raster
+        .chunks(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    // bottom row first
+    assert_eq!(&values[..3], &[0.001, 0.5, 1000.]);
+    assert_eq!(&values[6..12], &[12.5, 0.25, 0., 1., 2., 3.]);
+}
+
+#[test]
+fn rgbe_shares_one_exponent() {
+    assert_eq!(render::to_rgbe(0., 0., 0.), [0, 0, 0, 0]);
+    assert_eq!(render::to_rgbe(1., 0.5, 0.), [128, 64, 0, 129]);
+    assert_eq!(render::to_rgbe(12.5, 0.25, -1.), [200, 4, 0, 132]);
+}
+
+#[test]
+fn radiance_hdr_keeps_values_above_one() {
+    let c = bright_canvas();
+    let mut data = vec![];
+    c.write(&mut data, ImageType::HDR).unwrap();
+    let (header, raster) = split_header(&data, 4);
+
+    assert_eq!(header, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 2\n");
+    assert_eq!(raster.len(), 2 * 2 * 4);
+    for (rgbe, expected) in raster.chunks(4).zip(c.pixels.chunks(3)) {
+        let scale = 2f64.powi(rgbe[3] as i32 - 136);
+        let largest = expected.iter().cloned().fold(0., f64::max);
+        for i in 0..3 {
+            let decoded = match rgbe[3] {
+                0 => 0.,
+                _ => (rgbe[i] as f64 + 0.5) * scale,
+            };
+            assert!((decoded - expected[i]).abs() <= largest / 128.);
+        }
+    }
+}
+
+#[test]
+fn openexr_round_trips_through_a_decoder() {
+    let path = std::env::temp_dir().join("ray_tracer_canvas_exr_test.exr");
+    let c = bright_canvas();
+    c.save(&path).unwrap();
+
+    let image = exr::prelude::read_first_rgba_layer_from_file(
+        &path,
+        |resolution, _| vec![vec![(0., 0., 0.); resolution.width()]; resolution.height()],
+        |pixels: &mut Vec<Vec<(f32, f32, f32)>>, position, (r, g, b, _): (f32, f32, f32, f32)| {
+            pixels[position.y()][position.x()] = (r, g, b)
+        },
+    )
+    .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let pixels = image.layer_data.channel_data.pixels;
+
+    assert_eq!(pixels.len(), 2);
+    assert_eq!(pixels[0], vec![(12.5, 0.25, 0.), (1., 2., 3.)]);
+    assert_eq!(pixels[1], vec![(0.001, 0.5, 1000.), (0., 0., 0.)]);
+}
+
+#[test]
+fn empty_canvases_cannot_be_written_as_openexr() {
+    for c in [canvas(0, 2), canvas(2, 0)] {
+        let mut bytes = vec![];
+        let error = c.write(&mut bytes, ImageType::EXR).err().unwrap();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(bytes.is_empty());
+    }
+}
+
+#[test]
+fn hdr_formats_are_inferred_from_the_extension() {
+    assert_eq!(ImageType::from_path("a.pfm").unwrap(), ImageType::PFM);
+    assert_eq!(ImageType::from_path("a.hdr").unwrap(), ImageType::HDR);
+    assert_eq!(ImageType::from_path("a.EXR").unwrap(), ImageType::EXR);
+}
+
+#[test]
+fn linear_pngs_keep_their_values_and_load_back_unchanged() {
+    let mut c = canvas(2, 1);
+    c.write_pixel(0, 0, color(0.5, 0.25, 0.)).unwrap();
+    c.write_pixel(1, 0, color(1., 0.75, 0.1)).unwrap();
+
+    for image_type in [ImageType::PNG, ImageType::PNG16] {
+        let path = std::env::temp_dir().join("ray_tracer_canvas_linear_test.png");
+        c.save_with(&path, image_type, Transfer::Linear).unwrap();
+        let loaded = Canvas::load(&path).unwrap();
+
+        let step = if image_type == ImageType::PNG {
+            255.
+        } else {
+            65535.
+        };
+        for (a, b) in loaded.pixels.iter().zip(&c.pixels) {
+            assert!(
+                (a - b).abs() <= 0.5 / step,
+                "{:?}: {} != {}",
+                image_type,
+                a,
+                b
+            );
+        }
+    }
+}
+
+#[test]
+fn linear_binary_ppms_skip_the_srgb_curve() {
+    let mut c = canvas(1, 1);
+    c.write_pixel(0, 0, color(0.5, 0., 1.)).unwrap();
+    let mut bytes = vec![];
+    c.write_with(&mut bytes, ImageType::PPMBinary, Transfer::Linear)
+        .unwrap();
+
+    assert_eq!(bytes, b"P6\n1 1\n255\n\x80\x00\xff".to_vec());
+}
@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use ray_tracer::{
+    canvas::canvas,
+    color::color,
+    diagnostics::*,
+    light::point_light,
+    media::{fog, medium},
+    pattern::{checker_pattern, marble_pattern, perturbed_pattern, stripe_pattern},
+    shapes::object::Object,
+    texture::{image_texture, texture_map, UvMapping},
+    transforms::{scaling, translation},
+    tuple::point,
+    world::{default_world, world},
+};
+
+#[test]
+fn default_world_is_valid() {
+    assert_eq!(default_world().validate(), vec![]);
+}
+
+#[test]
+fn singular_transforms_are_errors() {
+    let mut w = default_world();
+    w.objects[1].transform = scaling(1., 0., 1.);
+    let diagnostics = w.validate();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].is_error());
+    assert_eq!(diagnostics[0].subject, "object 1");
+    assert_eq!(diagnostics[0].uuid, Some(w.objects[1].uuid));
+    assert_eq!(
+        diagnostics[0].to_string(),
+        format!(
+            "error: object 1 ({}): transform cannot be inverted (is something scaled by 0?)",
+            w.objects[1].uuid
+        )
+    );
+}
+
+#[test]
+fn singular_pattern_transforms_are_errors() {
+    let mut w = default_world();
+    let mut stripes = stripe_pattern(color(1., 1., 1.), color(0., 0., 0.));
+    stripes.transform = scaling(0., 1., 1.);
+    w.objects[0].material.pattern = Some(Arc::new(stripes));
+    let diagnostics = w.validate();
+
+    assert!(has_errors(&diagnostics));
+    assert_eq!(
+        diagnostics[0].message,
+        "pattern transform cannot be inverted (is something scaled by 0?)"
+    );
+}
+
+#[test]
+fn singular_nested_pattern_transforms_are_errors() {
+    let mut w = default_world();
+    let mut inner = stripe_pattern(color(1., 1., 1.), color(0., 0., 0.));
+    inner.transform = scaling(1., 0., 1.);
+    let checkers = checker_pattern(color(1., 0., 0.), inner);
+    w.objects[0].material.pattern = Some(Arc::new(perturbed_pattern(Box::new(checkers))));
+    let diagnostics = w.validate();
+
+    assert_eq!(
+        diagnostics[0].message,
+        "pattern.inner.b transform cannot be inverted (is something scaled by 0?)"
+    );
+}
+
+#[test]
+fn zero_pattern_scales_are_errors() {
+    let mut w = default_world();
+    let mut marble = marble_pattern(color(1., 1., 1.), color(0., 0., 0.));
+    marble.scale = 0.;
+    w.objects[0].material.specular_pattern = Some(Arc::new(marble));
+    let diagnostics = w.validate();
+
+    assert_eq!(
+        diagnostics[0].message,
+        "specular pattern scale must be finite and not 0, found 0"
+    );
+}
+
+#[test]
+fn empty_image_textures_are_errors() {
+    let mut w = default_world();
+    let mut texture = image_texture(canvas(1, 1)).unwrap();
+    texture.canvas = canvas(0, 0);
+    w.objects[0].material.pattern =
+        Some(Arc::new(texture_map(UvMapping::Planar, Box::new(texture))));
+    let diagnostics = w.validate();
+
+    assert_eq!(
+        diagnostics[0].message,
+        "pattern image texture is empty (0x0)"
+    );
+}
+
+#[test]
+fn non_positive_refractive_index_is_an_error() {
+    let mut w = default_world();
+    w.objects[0].material.refractive_index = 0.;
+    let diagnostics = w.validate();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].subject, "object 0");
+    assert_eq!(
+        diagnostics[0].message,
+        "refractive_index must be positive, found 0"
+    );
+}
+
+#[test]
+fn light_inside_an_opaque_object_is_a_warning() {
+    let mut w = default_world();
+    w.light = point_light(point(0., 0., 0.), color(1., 1., 1.));
+    let diagnostics = w.validate();
+
+    assert_eq!(diagnostics.len(), 2);
+    assert!(!has_errors(&diagnostics));
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(
+        diagnostics[0].message,
+        "the light is inside this opaque object"
+    );
+
+    // a light inside glass is fine
+    w.objects = vec![Object::new_glass_sphere()];
+    assert_eq!(w.validate(), vec![]);
+}
+
+#[test]
+fn out_of_range_fractions_are_warnings() {
+    let mut w = default_world();
+    w.objects[0].material.reflective = 1.5;
+    let diagnostics = w.validate();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].message, "reflective is 1.5, expected 0 to 1");
+}
+
+#[test]
+fn duplicate_uuids_are_errors() {
+    let sphere = Object::new_sphere();
+    let mut copy = sphere.clone();
+    copy.transform = translation(3., 0., 0.);
+    let w = world(
+        point_light(point(-10., 10., -10.), color(1., 1., 1.)),
+        vec![sphere, copy],
+    );
+    let diagnostics = w.validate();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].subject, "object 1");
+    assert_eq!(diagnostics[0].message, "has the same uuid as object 0");
+}
+
+#[test]
+fn broken_media_and_fog_are_errors() {
+    let mut w = default_world();
+    w.fog = Some(fog(color(1., 1., 1.), -0.1));
+    let mut smoke = medium(Object::new_sphere(), 0.1, 0.1);
+    smoke.step_size = 0.;
+    w.media.push(smoke);
+    let messages: Vec<String> = w.validate().iter().map(|d| d.to_string()).collect();
+
+    assert_eq!(
+        messages[0],
+        "error: fog: density must not be negative, found -0.1"
+    );
+    assert!(messages[1].starts_with("error: medium 0 ("));
+    assert!(messages[1].ends_with("): step_size must be positive, found 0"));
+}
+
+#[test]
+fn empty_and_unlit_worlds_are_warnings() {
+    let w = world(point_light(point(0., 0., 0.), color(0., 0., 0.)), vec![]);
+    let messages: Vec<String> = w.validate().iter().map(|d| d.to_string()).collect();
+
+    assert_eq!(
+        messages,
+        vec![
+            "warning: light: intensity is black, the scene will be unlit",
+            "warning: world: there are no objects to render",
+        ]
+    );
+}
@@ -0,0 +1,270 @@
+use std::sync::Arc;
+
+use ray_tracer::{
+    bump::ripple_bump,
+    camera::{camera, render, view_transform},
+    canvas::canvas,
+    color::{color, Color},
+    json::*,
+    light::point_light,
+    material::{microfacet_material, Dispersion},
+    matrix::{identity, InverseCache, Matrix},
+    media::fog,
+    pattern::{checker_pattern, marble_pattern, stripe_pattern, Pattern},
+    scene::Scene,
+    shapes::object::Object,
+    texture::{image_texture, texture_map, uv_checkers, Filter, ImageTexture, UvMapping},
+    transforms::{rotation_x, scaling, translation},
+    tuple::{point, vector, Tuple},
+    world::world,
+};
+
+fn scene() -> Scene {
+    let mut floor = Object::new_plane();
+    let mut stripes = stripe_pattern(
+        checker_pattern(color(1., 1., 1.), color(0., 0., 0.)),
+        color(1., 0., 0.),
+    );
+    stripes.transform = scaling(0.5, 0.5, 0.5);
+    floor.material.pattern = Some(Arc::new(stripes));
+    floor.material.reflective = 0.3;
+    floor.material.bump = Some(Arc::new(ripple_bump(0.05, 1.)));
+
+    let mut ball = Object::new_sphere();
+    ball.transform = translation(0., 1., 0.) * rotation_x(0.5);
+    ball.material.pattern = Some(Arc::new(texture_map(
+        UvMapping::Spherical,
+        Box::new(uv_checkers(16., 8., color(0., 0.5, 0.), color(1., 1., 1.))),
+    )));
+    ball.material.specular_pattern = Some(Arc::new(marble_pattern(
+        color(0.2, 0.2, 0.2),
+        color(0.9, 0.9, 0.9),
+    )));
+
+    let mut glass = Object::new_sphere();
+    glass.transform = translation(1.5, 0.5, -0.5) * scaling(0.5, 0.5, 0.5);
+    glass.material.transparency = 0.9;
+    glass.material.refractive_index = 1.5;
+    glass.material.dispersion = Dispersion::Abbe(40.);
+
+    let mut w = world(
+        point_light(point(-10., 10., -10.), color(1., 1., 1.)),
+        vec![floor, ball, glass],
+    );
+    w.fog = Some(fog(color(0.5, 0.5, 0.5), 0.02));
+
+    let mut c = camera(40, 20, 1.2);
+    c.transform = view_transform(point(0., 1.5, -5.), point(0., 1., 0.), vector(0., 1., 0.));
+
+    return Scene {
+        world: w,
+        camera: c,
+    };
+}
+
+fn round_trip(scene: &Scene) -> Scene {
+    scene_from_json(&scene_to_json(scene).unwrap()).unwrap()
+}
+
+#[test]
+fn round_trip_keeps_objects_and_their_ids() {
+    let original = scene();
+    let copy = round_trip(&original);
+
+    assert_eq!(copy.world.objects.len(), 3);
+    for (a, b) in original.world.objects.iter().zip(&copy.world.objects) {
+        assert_eq!(a.uuid, b.uuid);
+        assert_eq!(a.transform, b.transform);
+        assert_eq!(a.material.reflective, b.material.reflective);
+    }
+    assert_eq!(
+        copy.world.objects[2].material.dispersion,
+        Dispersion::Abbe(40.)
+    );
+    assert_eq!(copy.world.fog.unwrap().density, 0.02);
+    assert_eq!(copy.camera.transform, original.camera.transform);
+    assert_eq!(copy.camera.hsize, 40);
+}
+
+#[test]
+fn round_trip_keeps_nested_patterns_and_bumps() {
+    let original = scene();
+    let copy = round_trip(&original);
+
+    for (a, b) in original.world.objects.iter().zip(&copy.world.objects) {
+        for p in [
+            point(0.1, 0.01, 0.1),
+            point(0.3, 0.01, 0.7),
+            point(0.8, 1.2, -0.4),
+        ] {
+            assert_eq!(a.material.color_at(a, p), b.material.color_at(b, p));
+            assert_eq!(a.material.specular_at(a, p), b.material.specular_at(b, p));
+        }
+        assert_eq!(
+            a.normal_at(point(0.3, 0., 0.2)),
+            b.normal_at(point(0.3, 0., 0.2))
+        );
+    }
+}
+
+#[test]
+fn round_tripped_scene_renders_the_same_image() {
+    let original = scene();
+    let copy = round_trip(&original);
+
+    let expected = render(original.camera, original.world);
+    let actual = render(copy.camera, copy.world);
+
+    assert_eq!(expected.pixels, actual.pixels);
+}
+
+#[test]
+fn patterns_are_tagged_with_their_type() {
+    let json = scene_to_json(&scene()).unwrap();
+
+    assert!(json.contains("\"type\": \"stripes\""));
+    assert!(json.contains("\"type\": \"checkers\""));
+    assert!(json.contains("\"type\": \"ripples\""));
+}
+
+#[derive(Clone)]
+struct Unsaved {
+    transform: Matrix<f64>,
+    inverse: InverseCache,
+}
+
+impl Pattern for Unsaved {
+    fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    fn inverse_cache(&self) -> &InverseCache {
+        &self.inverse
+    }
+
+    fn at_point(&self, _point: Tuple) -> Color {
+        color(1., 1., 1.)
+    }
+}
+
+#[test]
+fn patterns_without_json_support_are_reported() {
+    let mut s = scene();
+    s.world.objects[0].material.pattern = Some(Arc::new(Unsaved {
+        transform: identity(),
+        inverse: InverseCache::default(),
+    }));
+
+    assert_eq!(
+        scene_to_json(&s).err().unwrap().to_string(),
+        "this pattern cannot be serialized"
+    );
+}
+
+#[test]
+fn unknown_pattern_types_are_reported() {
+    let json = scene_to_json(&scene())
+        .unwrap()
+        .replace("\"type\": \"stripes\"", "\"type\": \"plaid\"");
+
+    let error = scene_from_json(&json).err().unwrap();
+    assert!(error.line.is_some());
+    assert_eq!(error.message, "unknown pattern type 'plaid'");
+}
+
+#[test]
+fn shared_patterns_are_still_shared_after_a_round_trip() {
+    let mut s = scene();
+    let shared = s.world.objects[0].material.clone();
+    s.world.objects[1].material = shared;
+    assert!(s.world.objects[0].material == s.world.objects[1].material);
+
+    let copy = round_trip(&s);
+    assert!(copy.world.objects[0].material == copy.world.objects[1].material);
+    assert!(copy.world.objects[0].material != copy.world.objects[2].material);
+}
+
+#[test]
+fn image_textures_are_saved_as_their_path() {
+    let path = std::env::temp_dir().join("ray_tracer_json_texture_test.ppm");
+    let mut image = canvas(2, 1);
+    image.write_pixel(0, 0, color(1., 0., 0.)).unwrap();
+    image.save(&path).unwrap();
+
+    let mut s = scene();
+    let mut texture = ImageTexture::load(&path).unwrap();
+    texture.filter = Filter::Bilinear;
+    s.world.objects[1].material.pattern =
+        Some(Arc::new(texture_map(UvMapping::Planar, Box::new(texture))));
+
+    let json = scene_to_json(&s).unwrap();
+    let copy = scene_from_json(&json);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(json.contains("ray_tracer_json_texture_test.ppm"));
+    assert!(!json.contains("pixels"));
+    let copy = copy.unwrap();
+    let (a, b) = (&s.world.objects[1], &copy.world.objects[1]);
+    for p in [
+        point(0.1, 1., 0.2),
+        point(0.5, 1., 0.5),
+        point(0.8, 1., 0.4),
+    ] {
+        assert_eq!(a.material.color_at(a, p), b.material.color_at(b, p));
+    }
+}
+
+#[test]
+fn image_textures_without_a_file_must_fill_their_size() {
+    let mut s = scene();
+    let texture = image_texture(canvas(2, 2)).unwrap();
+    s.world.objects[1].material.pattern =
+        Some(Arc::new(texture_map(UvMapping::Planar, Box::new(texture))));
+    let json = scene_to_json(&s).unwrap();
+    assert!(scene_from_json(&json).is_ok());
+
+    let short = json.replacen("\"width\": 2", "\"width\": 3", 1);
+    let error = scene_from_json(&short).err().unwrap();
+    assert_eq!(error.message, "a 3x2 image needs 18 samples, found 12");
+}
+
+#[test]
+fn transforms_must_be_4x4() {
+    let flat = Matrix::from(vec![vec![1., 0., 0.], vec![0., 1., 0.], vec![0., 0., 1.]]);
+
+    let mut s = scene();
+    s.world.objects[2].transform = flat.clone();
+    let json = scene_to_json(&s).unwrap();
+    assert!(scene_from_json(&json)
+        .err()
+        .unwrap()
+        .message
+        .starts_with("transforms must be 4x4 matrices"));
+
+    let mut s = scene();
+    let mut stripes = stripe_pattern(color(1., 1., 1.), color(0., 0., 0.));
+    stripes.transform = flat;
+    s.world.objects[2].material.pattern = Some(Arc::new(stripes));
+    let json = scene_to_json(&s).unwrap();
+    assert!(scene_from_json(&json)
+        .err()
+        .unwrap()
+        .message
+        .starts_with("transforms must be 4x4 matrices"));
+}
+
+#[test]
+fn round_tripped_glossy_scene_renders_the_same_image() {
+    let mut original = scene();
+    original.world.objects[0].material.roughness = 0.3;
+    original.world.objects[0].material.glossy_samples = 4;
+    original.world.objects[1].material = microfacet_material(color(1., 0.78, 0.34), 1., 0.4, 1.5);
+    original.world.objects[1].material.glossy_samples = 4;
+    original.camera.samples = 2;
+    let copy = round_trip(&original);
+
+    let expected = render(original.camera, original.world);
+    let actual = render(copy.camera, copy.world);
+
+    assert_eq!(expected.pixels, actual.pixels);
+}
@@ -0,0 +1,92 @@
+use ray_tracer::{
+    color::color,
+    library::*,
+    material::{material, ShadingModel},
+    shapes::object::Object,
+};
+
+#[test]
+fn library_comes_with_presets() {
+    let library = material_library();
+
+    assert_eq!(
+        library.names(),
+        vec![
+            "air_bubble",
+            "chrome",
+            "diamond",
+            "glass",
+            "gold",
+            "matte",
+            "mirror",
+            "plastic",
+            "water"
+        ]
+    );
+    assert!(MaterialLibrary::new().names().is_empty());
+}
+
+#[test]
+fn transparent_presets_use_real_refractive_indices() {
+    let library = material_library();
+    let index = |name| library.get(name).unwrap().refractive_index;
+
+    assert_eq!(index("glass"), 1.5);
+    assert_eq!(index("water"), 1.333);
+    assert_eq!(index("diamond"), 2.417);
+    assert!(index("air_bubble") > 1. && index("air_bubble") < 1.001);
+    for name in ["glass", "water", "diamond", "air_bubble"] {
+        assert!(library.get(name).unwrap().transparency > 0.);
+    }
+}
+
+#[test]
+fn opaque_presets() {
+    let library = material_library();
+
+    let mirror = library.get("mirror").unwrap();
+    assert_eq!(mirror.reflective, 1.);
+    assert_eq!(mirror.transparency, 0.);
+
+    let gold = library.get("gold").unwrap();
+    assert_eq!(gold.color, color(1., 0.766, 0.336));
+    assert!(matches!(gold.model, ShadingModel::Microfacet { metallic, .. } if metallic == 1.));
+
+    let matte = library.get("matte").unwrap();
+    assert_eq!(matte.specular, 0.);
+    assert_eq!(matte.reflective, 0.);
+}
+
+#[test]
+fn unknown_names_are_not_found() {
+    let library = material_library();
+
+    assert!(!library.contains("unobtainium"));
+    assert!(library.get("unobtainium").is_none());
+}
+
+#[test]
+fn library_entries_can_be_added_and_replaced() {
+    let mut library = material_library();
+    let mut custom = material();
+    custom.color = color(0.2, 0.4, 0.6);
+
+    library.insert("custom", custom.clone());
+    library.insert("glass", custom.clone());
+
+    assert!(library.get("custom").unwrap() == custom);
+    assert!(library.get("glass").unwrap() == custom);
+}
+
+#[test]
+fn objects_can_take_a_material_by_name() {
+    let library = material_library();
+    let mut sphere = Object::new_sphere();
+
+    sphere.set_material_from(&library, "diamond").unwrap();
+    assert_eq!(sphere.material.refractive_index, 2.417);
+
+    let result = sphere.set_material_from(&library, "unobtainium");
+    assert_eq!(result, Err("unknown material 'unobtainium'".to_string()));
+    assert_eq!(sphere.material.refractive_index, 2.417);
+}
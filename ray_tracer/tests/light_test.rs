@@ -1,10 +1,14 @@
+use std::sync::Arc;
+
 use ray_tracer::{
     color::color,
-    light::{is_shadowed, lighting, point_light},
-    material::material,
+    light::{is_shadowed, light_transmittance, lighting, point_light},
+    material::{material, microfacet_material, Material},
+    pattern::solid_pattern,
     shapes::object::Object,
+    transforms::translation,
     tuple::{point, vector},
-    world::default_world,
+    world::{default_world, world},
 };
 
 #[test]
@@ -37,6 +41,27 @@ fn lighting_with_eye_between_light_and_surface() {
     assert_eq!(result, color(1.9, 1.9, 1.9));
 }
 
+#[test]
+fn lighting_with_specular_driven_by_a_pattern() {
+    let mut m = material();
+    m.specular_pattern = Some(Arc::new(solid_pattern(color(0., 0., 0.))));
+    let position = point(0., 0., 0.);
+    let eyev = vector(0., 0., -1.);
+    let normalv = vector(0., 0., -1.);
+    let light = point_light(point(0., 0., -10.), color(1., 1., 1.));
+    let result = lighting(
+        &m,
+        &Object::new_sphere(),
+        &light,
+        position,
+        eyev,
+        normalv,
+        false,
+    );
+
+    assert_eq!(result, color(1., 1., 1.));
+}
+
 #[test]
 fn lighting_with_eye_between_light_and_surface_and_eye_offset_45() {
     let sqrt_of_2_over_2 = (2.0 as f64).sqrt() / 2.0;
@@ -171,3 +196,139 @@ fn the_is_no_shadow_when_object_is_behind_point() {
 
     assert_eq!(is_shadowed(&w, p), false);
 }
+
+#[test]
+fn microfacet_lighting_in_shadow_is_only_ambient() {
+    let m = microfacet_material(color(1., 0.5, 0.), 0., 0.5, 1.5);
+    let light = point_light(point(0., 0., -10.), color(1., 1., 1.));
+    let result = lighting(
+        &m,
+        &Object::new_sphere(),
+        &light,
+        point(0., 0., 0.),
+        vector(0., 0., -1.),
+        vector(0., 0., -1.),
+        true,
+    );
+
+    assert_eq!(result, color(0.1, 0.05, 0.));
+}
+
+#[test]
+fn microfacet_lighting_with_light_behind_surface() {
+    let m = microfacet_material(color(1., 1., 1.), 0., 0.5, 1.5);
+    let light = point_light(point(0., 0., 10.), color(1., 1., 1.));
+    let result = lighting(
+        &m,
+        &Object::new_sphere(),
+        &light,
+        point(0., 0., 0.),
+        vector(0., 0., -1.),
+        vector(0., 0., -1.),
+        false,
+    );
+
+    assert_eq!(result, color(0.1, 0.1, 0.1));
+}
+
+#[test]
+fn microfacet_metal_tints_its_highlight_and_has_no_diffuse() {
+    let gold = color(1., 0.78, 0.34);
+    let light = point_light(point(0., 0., -10.), color(1., 1., 1.));
+    let shade = |m: &Material, eyev| {
+        lighting(
+            m,
+            &Object::new_sphere(),
+            &light,
+            point(0., 0., 0.),
+            eyev,
+            vector(0., 0., -1.),
+            false,
+        )
+    };
+
+    let mut metal = microfacet_material(gold, 1., 0.3, 1.5);
+    metal.ambient = 0.;
+    let highlight = shade(&metal, vector(0., 0., -1.));
+    assert!(highlight.r > highlight.g && highlight.g > highlight.b);
+
+    let off_specular = shade(&metal, vector(0., 0.8, -0.6));
+    assert!(off_specular.r < highlight.r * 0.1);
+}
+
+#[test]
+fn rougher_microfacet_surfaces_have_dimmer_highlights() {
+    let light = point_light(point(0., 0., -10.), color(1., 1., 1.));
+    let shade = |roughness| {
+        lighting(
+            &microfacet_material(color(0.2, 0.2, 0.2), 0., roughness, 1.5),
+            &Object::new_sphere(),
+            &light,
+            point(0., 0., 0.),
+            vector(0., 0., -1.),
+            vector(0., 0., -1.),
+            false,
+        )
+    };
+
+    assert!(shade(0.1).r > shade(0.6).r);
+}
+
+#[test]
+fn a_roughness_pattern_roughens_microfacet_highlights() {
+    let light = point_light(point(0., 0., -10.), color(1., 1., 1.));
+    let shade = |m: &Material| {
+        lighting(
+            m,
+            &Object::new_sphere(),
+            &light,
+            point(0., 0., 0.),
+            vector(0., 0., -1.),
+            vector(0., 0., -1.),
+            false,
+        )
+    };
+
+    let mut m = microfacet_material(color(0.2, 0.2, 0.2), 0., 0.1, 1.5);
+    let smooth = shade(&m);
+    m.roughness_pattern = Some(Arc::new(solid_pattern(color(1., 1., 1.))));
+
+    assert!(shade(&m).r < smooth.r);
+}
+
+fn half_transparent_sphere_at(z: f64) -> Object {
+    let mut s = Object::new_sphere();
+    s.transform = translation(0., 0., z);
+    s.material.transparency = 0.5;
+    return s;
+}
+
+#[test]
+fn a_transparent_sphere_filters_light_once() {
+    let light = point_light(point(0., 0., -10.), color(1., 1., 1.));
+    let w = world(light, vec![half_transparent_sphere_at(0.)]);
+
+    assert_eq!(light_transmittance(&w, point(0., 0., 5.)), 0.5);
+}
+
+#[test]
+fn each_transparent_object_in_the_way_filters_light() {
+    let light = point_light(point(0., 0., -10.), color(1., 1., 1.));
+    let w = world(
+        light,
+        vec![
+            half_transparent_sphere_at(0.),
+            half_transparent_sphere_at(-4.),
+        ],
+    );
+
+    assert_eq!(light_transmittance(&w, point(0., 0., 5.)), 0.25);
+}
+
+#[test]
+fn light_leaving_a_transparent_sphere_is_filtered_once() {
+    let light = point_light(point(0., 0., -10.), color(1., 1., 1.));
+    let w = world(light, vec![half_transparent_sphere_at(0.)]);
+
+    assert_eq!(light_transmittance(&w, point(0., 0., 0.)), 0.5);
+}
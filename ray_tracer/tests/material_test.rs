@@ -1,4 +1,12 @@
-use ray_tracer::{color::color, material::*};
+use std::sync::Arc;
+
+use ray_tracer::{
+    color::{color, Channel, CHANNELS},
+    material::*,
+    pattern::{checker_pattern, solid_pattern, stripe_pattern, Pattern},
+    shapes::object::Object,
+    tuple::point,
+};
 
 #[test]
 fn creating_default_material() {
@@ -12,4 +20,133 @@ fn creating_default_material() {
     assert_eq!(material.reflective, 0.);
     assert_eq!(material.transparency, 0.);
     assert_eq!(material.refractive_index, 1.);
+    assert_eq!(material.absorption, color(0., 0., 0.));
+    assert_eq!(material.roughness, 0.);
+    assert_eq!(material.glossy_samples, 8);
+}
+
+#[test]
+fn attenuation_follows_beer_lambert_per_channel() {
+    let mut material = material();
+    material.absorption = color(0., 0.5, 1.);
+
+    assert_eq!(material.attenuation(0.), color(1., 1., 1.));
+    assert_eq!(
+        material.attenuation(2.),
+        color(1., (-1_f64).exp(), (-2_f64).exp())
+    );
+}
+
+#[test]
+fn non_dispersive_material_uses_one_index_for_all_channels() {
+    let mut material = material();
+    material.refractive_index = 1.5;
+
+    assert!(!material.is_dispersive());
+    for channel in CHANNELS.iter() {
+        assert_eq!(material.refractive_index_for(*channel), 1.5);
+    }
+}
+
+#[test]
+fn abbe_number_spreads_indices_around_n_d() {
+    let mut material = material();
+    material.refractive_index = 1.5168;
+    material.dispersion = Dispersion::Abbe(64.17);
+
+    let red = material.refractive_index_for(Channel::Red);
+    let green = material.refractive_index_for(Channel::Green);
+    let blue = material.refractive_index_for(Channel::Blue);
+
+    assert!(material.is_dispersive());
+    assert_eq!(green, 1.5168);
+    assert!(red < green && green < blue);
+    assert!(((blue - red) - 0.5168 / 64.17).abs() < 0.00001);
+}
+
+#[test]
+fn per_channel_indices_are_used_directly() {
+    let mut material = material();
+    material.dispersion = Dispersion::PerChannel(2.40, 2.42, 2.45);
+
+    assert_eq!(material.refractive_index_for(Channel::Red), 2.40);
+    assert_eq!(material.refractive_index_for(Channel::Green), 2.42);
+    assert_eq!(material.refractive_index_for(Channel::Blue), 2.45);
+}
+
+#[test]
+fn scalar_properties_default_to_their_constant() {
+    let mut material = material();
+    material.reflective = 0.3;
+    material.transparency = 0.4;
+    material.roughness = 0.2;
+    let object = Object::new_sphere();
+    let p = point(0.5, 0., 0.);
+
+    assert_eq!(material.reflective_at(&object, p), 0.3);
+    assert_eq!(material.specular_at(&object, p), 0.9);
+    assert_eq!(material.transparency_at(&object, p), 0.4);
+    assert_eq!(material.roughness_at(&object, p), 0.2);
+}
+
+#[test]
+fn scalar_patterns_override_the_constant_per_point() {
+    let mut material = material();
+    material.reflective = 0.3;
+    material.reflective_pattern = Some(Arc::new(checker_pattern(
+        color(0., 0., 0.),
+        color(1., 1., 1.),
+    )));
+    material.roughness_pattern = Some(Arc::new(solid_pattern(color(0.1, 0.2, 0.6))));
+    let object = Object::new_sphere();
+
+    assert_eq!(material.reflective_at(&object, point(0.5, 0.5, 0.5)), 0.);
+    assert_eq!(material.reflective_at(&object, point(1.5, 0.5, 0.5)), 1.);
+    assert!((material.roughness_at(&object, point(0., 0., 0.)) - 0.3).abs() < 0.00001);
+}
+
+#[test]
+fn microfacet_roughness_comes_from_the_model_unless_patterned() {
+    let mut material = microfacet_material(color(1., 1., 1.), 0., 0.4, 1.5);
+    material.roughness = 0.9;
+    let object = Object::new_sphere();
+    assert_eq!(material.roughness_at(&object, point(0., 0., 0.)), 0.4);
+
+    material.roughness_pattern = Some(Arc::new(solid_pattern(color(1., 1., 1.))));
+    assert_eq!(material.roughness_at(&object, point(0., 0., 0.)), 1.);
+}
+
+#[test]
+fn cloning_a_material_keeps_its_patterns() {
+    let mut material = material();
+    material.pattern = Some(Arc::new(checker_pattern(
+        color(1., 1., 1.),
+        color(0., 0., 0.),
+    )));
+    material.specular_pattern = Some(Arc::new(solid_pattern(color(0., 0., 0.))));
+    let object = Object::new_sphere();
+    let p = point(1.5, 0.5, 0.5);
+
+    let copy = material.clone();
+    assert_eq!(copy.color_at(&object, p), color(0., 0., 0.));
+    assert_eq!(copy.specular_at(&object, p), 0.);
+    assert!(copy == material);
+}
+
+#[test]
+fn materials_compare_patterns_by_identity() {
+    let stripes: Arc<dyn Pattern> = Arc::new(stripe_pattern(color(1., 1., 1.), color(0., 0., 0.)));
+    let mut a = material();
+    let mut b = material();
+    a.pattern = Some(stripes.clone());
+    assert!(a != b);
+
+    b.pattern = Some(stripes);
+    assert!(a == b);
+
+    b.pattern = Some(Arc::new(stripe_pattern(
+        color(1., 1., 1.),
+        color(0., 0., 0.),
+    )));
+    assert!(a != b);
 }
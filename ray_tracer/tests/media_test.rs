@@ -0,0 +1,112 @@
+use std::f64::consts::PI;
+
+use ray_tracer::{
+    color::color,
+    light::point_light,
+    media::*,
+    ray::ray,
+    shapes::object::Object,
+    transforms::{rotation_x, translation},
+    tuple::{point, vector},
+    world::{default_world, world},
+};
+
+#[test]
+fn fog_transmittance_falls_off_exponentially() {
+    let f = fog(color(0.5, 0.5, 0.5), 0.5);
+    assert_eq!(f.transmittance(0.), 1.);
+    assert!((f.transmittance(2.) - (-1_f64).exp()).abs() < 0.00001);
+}
+
+#[test]
+fn fog_without_density_leaves_colors_untouched() {
+    let f = fog(color(0.5, 0.5, 0.5), 0.);
+    let c = color(0.2, 0.4, 0.6);
+    assert_eq!(f.apply(c, 100.), c);
+    assert_eq!(f.apply(c, f64::INFINITY), c);
+}
+
+#[test]
+fn color_at_when_ray_misses_returns_fog_color() {
+    let mut w = default_world();
+    w.fog = Some(fog(color(0.7, 0.7, 0.8), 0.1));
+    let r = ray(point(0., 0., -5.), vector(0., 1., 0.));
+
+    assert_eq!(w.color_at(&r, 1), color(0.7, 0.7, 0.8));
+}
+
+#[test]
+fn color_at_blends_hit_with_fog_by_distance() {
+    let mut w = default_world();
+    let r = ray(point(0., 0., -5.), vector(0., 0., 1.));
+    let surface = w.color_at(&r, 1);
+
+    w.fog = Some(fog(color(1., 1., 1.), 0.1));
+    let transmittance = (-0.4_f64).exp();
+    let expected = surface * transmittance + color(1., 1., 1.) * (1. - transmittance);
+
+    assert_eq!(w.color_at(&r, 1), expected);
+}
+
+#[test]
+fn medium_segments_are_clipped_to_ray_and_hit() {
+    let m = medium(Object::new_sphere(), 1., 0.);
+
+    let r = ray(point(0., 0., -5.), vector(0., 0., 1.));
+    assert_eq!(m.segments(&r, f64::INFINITY), vec![(4., 6.)]);
+    assert_eq!(m.segments(&r, 5.), vec![(4., 5.)]);
+
+    let inside = ray(point(0., 0., 0.), vector(0., 0., 1.));
+    assert_eq!(m.segments(&inside, f64::INFINITY), vec![(0., 1.)]);
+}
+
+#[test]
+fn absorbing_medium_darkens_what_lies_behind_it() {
+    let mut backdrop = Object::new_plane();
+    backdrop.transform = translation(0., 0., 5.) * rotation_x(PI / 2.);
+    backdrop.material.ambient = 1.;
+    backdrop.material.diffuse = 0.;
+    backdrop.material.specular = 0.;
+
+    let mut w = world(
+        point_light(point(0., 0., -10.), color(1., 1., 1.)),
+        vec![backdrop],
+    );
+    let r = ray(point(0., 0., -5.), vector(0., 0., 1.));
+    let clear = w.color_at(&r, 1);
+
+    w.media.push(medium(Object::new_sphere(), 0.5, 0.));
+    let hazy = w.color_at(&r, 1);
+
+    assert_eq!(hazy, clear * (-1_f64).exp());
+}
+
+#[test]
+fn scattering_medium_glows_when_lit_and_darkens_in_shadow() {
+    let r = ray(point(0., 0., -5.), vector(0., 0., 1.));
+
+    let mut lit = world(point_light(point(0., 10., 0.), color(1., 1., 1.)), vec![]);
+    lit.media.push(medium(Object::new_sphere(), 0., 1.));
+    let glow = lit.color_at(&r, 1);
+    assert!(glow.r > 0.);
+
+    let mut blocker = Object::new_plane();
+    blocker.transform = translation(0., 5., 0.);
+    let mut shadowed = world(
+        point_light(point(0., 10., 0.), color(1., 1., 1.)),
+        vec![blocker],
+    );
+    shadowed.media.push(medium(Object::new_sphere(), 0., 1.));
+    assert_eq!(shadowed.color_at(&r, 1), color(0., 0., 0.));
+}
+
+#[test]
+fn media_with_a_zero_step_size_are_crossed_in_one_step() {
+    let r = ray(point(0., 0., -5.), vector(0., 0., 1.));
+    let mut w = world(point_light(point(0., 10., 0.), color(1., 1., 1.)), vec![]);
+    let mut m = medium(Object::new_sphere(), 0., 1.);
+    m.step_size = 0.;
+    w.media.push(m);
+
+    assert!(w.color_at(&r, 1).r > 0.);
+}
@@ -0,0 +1,82 @@
+use std::f64::consts::PI;
+
+use rand::{rngs::StdRng, SeedableRng};
+use ray_tracer::{color::color, microfacet::*, tuple::vector, utils::EPSILON};
+
+#[test]
+fn ggx_distribution_peaks_along_the_normal() {
+    let roughness: f64 = 0.5;
+    let alpha2 = roughness.powi(4);
+    assert!((distribution_ggx(1., roughness) - 1. / (PI * alpha2)).abs() < EPSILON);
+    assert!(distribution_ggx(1., roughness) > distribution_ggx(0.9, roughness));
+}
+
+#[test]
+fn rougher_surfaces_spread_the_distribution() {
+    assert!(distribution_ggx(1., 0.2) > distribution_ggx(1., 0.8));
+    assert!(distribution_ggx(0.5, 0.2) < distribution_ggx(0.5, 0.8));
+}
+
+#[test]
+fn smith_geometry_is_one_head_on_and_shrinks_at_grazing_angles() {
+    assert!((geometry_smith(1., 1., 0.5) - 1.).abs() < EPSILON);
+    assert!(geometry_smith(0.1, 1., 0.5) < geometry_smith(0.9, 1., 0.5));
+    assert_eq!(geometry_smith(0., 1., 0.5), 0.);
+}
+
+#[test]
+fn fresnel_schlick_goes_from_f0_to_white() {
+    let f0 = color(0.04, 0.04, 0.04);
+    assert_eq!(fresnel_schlick(1., f0), f0);
+    assert_eq!(fresnel_schlick(0., f0), color(1., 1., 1.));
+}
+
+#[test]
+fn base_reflectance_of_dielectrics_and_metals() {
+    let gold = color(1., 0.78, 0.34);
+    assert_eq!(base_reflectance(gold, 0., 1.5), color(0.04, 0.04, 0.04));
+    assert_eq!(base_reflectance(gold, 1., 1.5), gold);
+}
+
+#[test]
+fn orthonormal_basis_is_perpendicular_to_normal() {
+    for n in [
+        vector(0., 1., 0.),
+        vector(1., 0., 0.),
+        vector(1., 1., 1.).normalize(),
+    ]
+    .iter()
+    {
+        let (t, b) = orthonormal_basis(*n);
+        assert!(t.dot(*n).abs() < EPSILON);
+        assert!(b.dot(*n).abs() < EPSILON);
+        assert!(t.dot(b).abs() < EPSILON);
+        assert!((t.magnitude() - 1.).abs() < EPSILON);
+        assert!((b.magnitude() - 1.).abs() < EPSILON);
+    }
+}
+
+#[test]
+fn sampled_half_vectors_stay_in_the_hemisphere() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let n = vector(0., 1., 0.);
+
+    for _ in 0..100 {
+        let h = sample_ggx_half_vector(n, 0.6, &mut rng);
+        assert!(h.dot(n) >= 0.);
+        assert!((h.magnitude() - 1.).abs() < EPSILON);
+    }
+    assert_eq!(sample_ggx_half_vector(n, 0., &mut rng), n);
+}
+
+#[test]
+fn lobe_samples_stay_close_to_the_direction() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let direction = vector(0., 0., 1.);
+
+    for _ in 0..100 {
+        let sample = sample_lobe(direction, 0.1, &mut rng);
+        assert!((sample.magnitude() - 1.).abs() < EPSILON);
+        assert!(sample.dot(direction) > 0.99);
+    }
+}
@@ -0,0 +1,46 @@
+use ray_tracer::{noise::perlin, tuple::point};
+
+#[test]
+fn noise_is_zero_on_lattice_points() {
+    let n = perlin(0);
+    assert_eq!(n.noise(point(0., 0., 0.)), 0.);
+    assert_eq!(n.noise(point(3., -7., 12.)), 0.);
+}
+
+#[test]
+fn noise_is_deterministic_per_seed() {
+    let p = point(1.3, 4.7, -2.2);
+    assert_eq!(perlin(42).noise(p), perlin(42).noise(p));
+    assert_ne!(perlin(1).noise(p), perlin(2).noise(p));
+}
+
+#[test]
+fn noise_is_bounded_and_continuous() {
+    let n = perlin(7);
+    for i in 0..1000 {
+        let t = i as f64 * 0.137;
+        let p = point(t, t * 0.5 - 3., 10. - t * 0.25);
+        let value = n.noise(p);
+        assert!((-1.1..=1.1).contains(&value));
+
+        let nearby = n.noise(point(p.x + 0.001, p.y, p.z));
+        assert!((value - nearby).abs() < 0.01);
+    }
+}
+
+#[test]
+fn turbulence_is_never_negative() {
+    let n = perlin(3);
+    for i in 0..200 {
+        let t = i as f64 * 0.31;
+        assert!(n.turbulence(point(t, -t, t * 2.), 4) >= 0.);
+    }
+}
+
+#[test]
+fn a_single_octave_of_fractal_noise_is_plain_noise() {
+    let n = perlin(5);
+    let p = point(0.4, 1.9, -0.6);
+    assert_eq!(n.fractal(p, 1), n.noise(p));
+    assert_ne!(n.fractal(p, 3), n.noise(p));
+}
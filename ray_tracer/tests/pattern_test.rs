@@ -1,5 +1,6 @@
 use ray_tracer::{
     color::color,
+    noise::perlin,
     pattern::*,
     shapes::object::Object,
     transforms::{scaling, translation},
@@ -11,8 +12,8 @@ fn creating_a_stripe_pattern() {
     let a = color(1., 1., 1.);
     let b = color(0., 0., 0.);
     let pattern = stripe_pattern(a, b);
-    assert_eq!(pattern.a, a);
-    assert_eq!(pattern.b, b);
+    assert_eq!(pattern.a.at_point(point(0., 0., 0.)), a);
+    assert_eq!(pattern.b.at_point(point(0., 0., 0.)), b);
 }
 
 #[test]
@@ -68,6 +69,28 @@ fn stripes_with_a_pattern_transformation() {
     assert_eq!(pattern.at_object(&object, point(1.5, 0., 0.)), white);
 }
 
+#[test]
+fn replacing_a_pattern_transformation_after_sampling_takes_effect() {
+    let white = color(1., 1., 1.);
+    let black = color(0., 0., 0.);
+    let mut pattern = stripe_pattern(white, black);
+    let object = Object::new_sphere();
+    assert_eq!(pattern.at_object(&object, point(1.5, 0., 0.)), black);
+
+    pattern.transform = scaling(2., 2., 2.);
+    assert_eq!(pattern.at_object(&object, point(1.5, 0., 0.)), white);
+}
+
+#[test]
+fn cloned_stripes_keep_their_transformation() {
+    let mut pattern = stripe_pattern(color(1., 1., 1.), color(0., 0., 0.));
+    pattern.transform = scaling(2., 2., 2.);
+    let copy = pattern.clone();
+
+    assert_eq!(copy.transform, pattern.transform);
+    assert_eq!(copy.at_point(point(1.5, 0., 0.)), color(0., 0., 0.));
+}
+
 #[test]
 fn stripes_with_a_pattern_and_object_transformation() {
     let white = color(1., 1., 1.);
@@ -106,7 +129,7 @@ fn ring_pattern_should_extend_in_both_x_and_z() {
     assert_eq!(pattern.at_point(point(0., 0., 0.)), white);
     assert_eq!(pattern.at_point(point(1., 0., 0.)), black);
     assert_eq!(pattern.at_point(point(0., 0., 1.)), black);
-    assert_eq!(pattern.at_point(point(0.708, 0., 708.)), black);
+    assert_eq!(pattern.at_point(point(0.708, 0., 0.708)), black);
 }
 
 #[test]
@@ -138,3 +161,246 @@ fn checkers_should_repeat_in_z() {
     assert_eq!(pattern.at_point(point(0., 0., 0.99)), white);
     assert_eq!(pattern.at_point(point(0., 0., 1.01)), black);
 }
+
+#[test]
+fn perturbed_pattern_without_jitter_matches_inner_pattern() {
+    let white = color(1., 1., 1.);
+    let black = color(0., 0., 0.);
+    let mut pattern = perturbed_pattern(Box::new(stripe_pattern(white, black)));
+    pattern.amount = 0.;
+
+    for x in [0.1, 0.9, 1.2, -0.5].iter() {
+        let p = point(*x, 0.3, 0.7);
+        assert_eq!(
+            pattern.at_point(p),
+            stripe_pattern(white, black).at_point(p)
+        );
+    }
+}
+
+#[test]
+fn perturbed_pattern_respects_inner_transform() {
+    let white = color(1., 1., 1.);
+    let black = color(0., 0., 0.);
+    let mut inner = stripe_pattern(white, black);
+    inner.transform = scaling(2., 2., 2.);
+    let mut pattern = perturbed_pattern(Box::new(inner));
+    pattern.amount = 0.;
+
+    assert_eq!(pattern.at_point(point(1.5, 0., 0.)), white);
+    assert_eq!(pattern.at_point(point(2.5, 0., 0.)), black);
+}
+
+#[test]
+fn perturbed_pattern_moves_stripe_edges() {
+    let white = color(1., 1., 1.);
+    let black = color(0., 0., 0.);
+    let pattern = perturbed_pattern(Box::new(stripe_pattern(white, black)));
+    let plain = stripe_pattern(white, black);
+
+    let differs = (0..200)
+        .map(|i| point(1. + (i as f64 - 100.) * 0.001, i as f64 * 0.05, 0.3))
+        .any(|p| pattern.at_point(p) != plain.at_point(p));
+    assert!(differs);
+}
+
+#[test]
+fn noise_patterns_blend_between_their_colors() {
+    let a = color(0.2, 0.2, 0.2);
+    let b = color(0.8, 0.8, 0.8);
+    let patterns: Vec<Box<dyn Pattern>> = vec![
+        Box::new(turbulence_pattern(a, b)),
+        Box::new(marble_pattern(a, b)),
+        Box::new(wood_pattern(a, b)),
+    ];
+
+    for pattern in patterns.iter() {
+        for i in 0..100 {
+            let t = i as f64 * 0.173;
+            let c = pattern.at_point(point(t, t * 0.3, -t));
+            assert!(c.r >= a.r - 0.00001 && c.r <= b.r + 0.00001);
+        }
+    }
+}
+
+#[test]
+fn noise_patterns_depend_on_seed() {
+    let a = color(0., 0., 0.);
+    let b = color(1., 1., 1.);
+    let p = point(0.37, 1.21, -0.83);
+
+    let first = marble_pattern(a, b);
+    let mut second = marble_pattern(a, b);
+    second.noise = perlin(99);
+
+    assert_eq!(first.at_point(p), marble_pattern(a, b).at_point(p));
+    assert_ne!(first.at_point(p), second.at_point(p));
+}
+
+#[test]
+fn stripes_can_be_made_of_other_patterns() {
+    let white = color(1., 1., 1.);
+    let black = color(0., 0., 0.);
+    let red = color(1., 0., 0.);
+    let pattern = stripe_pattern(checker_pattern(white, black), red);
+
+    assert_eq!(pattern.at_point(point(0.2, 0., 0.2)), white);
+    assert_eq!(pattern.at_point(point(0.2, 0., 1.2)), black);
+    assert_eq!(pattern.at_point(point(1.5, 0., 0.5)), red);
+}
+
+#[test]
+fn nested_patterns_respect_their_own_transform() {
+    let white = color(1., 1., 1.);
+    let black = color(0., 0., 0.);
+    let mut inner = stripe_pattern(white, black);
+    inner.transform = scaling(0.5, 0.5, 0.5);
+    let outer = checker_pattern(inner, black);
+
+    assert_eq!(outer.at_point(point(0.25, 0., 0.)), white);
+    assert_eq!(outer.at_point(point(0.75, 0., 0.)), black);
+}
+
+#[test]
+fn nested_transforms_compose_with_the_parent() {
+    let white = color(1., 1., 1.);
+    let black = color(0., 0., 0.);
+    let mut inner = stripe_pattern(white, black);
+    inner.transform = scaling(0.5, 0.5, 0.5);
+    let mut outer = blended_pattern(inner, test_pattern());
+    outer.weight = 0.;
+    outer.transform = scaling(2., 2., 2.);
+    let object = Object::new_sphere();
+
+    // x = 3 is 1.5 in the parent's space and 3 in the stripes' own space
+    assert_eq!(outer.at_object(&object, point(3., 0., 0.)), black);
+    assert_eq!(outer.at_object(&object, point(0.5, 0., 0.)), white);
+}
+
+#[test]
+fn blended_pattern_averages_by_default() {
+    let white = color(1., 1., 1.);
+    let black = color(0., 0., 0.);
+    let mut shifted = stripe_pattern(white, black);
+    shifted.transform = translation(1., 0., 0.);
+    let pattern = blended_pattern(stripe_pattern(white, black), shifted);
+
+    assert_eq!(pattern.weight, 0.5);
+    assert_eq!(pattern.at_point(point(0.5, 0., 0.)), color(0.5, 0.5, 0.5));
+    assert_eq!(pattern.at_point(point(1.5, 0., 0.)), color(0.5, 0.5, 0.5));
+}
+
+#[test]
+fn blended_pattern_weights_toward_b() {
+    let pattern = BlendedPattern {
+        weight: 0.25,
+        ..blended_pattern(color(1., 0., 0.), color(0., 0., 1.))
+    };
+
+    assert_eq!(pattern.at_point(point(0., 0., 0.)), color(0.75, 0., 0.25));
+}
+
+#[test]
+fn patterns_accept_boxed_sub_patterns() {
+    let white = color(1., 1., 1.);
+    let inner: Box<dyn Pattern> = Box::new(solid_pattern(white));
+    let pattern = gradient_pattern(inner, color(0., 0., 0.));
+
+    assert_eq!(
+        pattern.at_point(point(0.25, 0., 0.)),
+        color(0.75, 0.75, 0.75)
+    );
+}
+
+#[test]
+fn rings_band_by_whole_units_of_distance() {
+    let white = color(1., 1., 1.);
+    let black = color(0., 0., 0.);
+    let pattern = ring_pattern(white, black);
+    assert_eq!(pattern.at_point(point(0.5, 0., 0.5)), white);
+    assert_eq!(pattern.at_point(point(1.5, 0., 0.)), black);
+    assert_eq!(pattern.at_point(point(0., 0., -2.5)), white);
+    assert_eq!(pattern.at_point(point(1.5, 0., 1.5)), white);
+}
+
+#[test]
+fn radial_gradient_fades_with_distance_from_origin() {
+    let white = color(1., 1., 1.);
+    let black = color(0., 0., 0.);
+    let pattern = radial_gradient_pattern(white, black);
+    assert_eq!(pattern.at_point(point(0., 0., 0.)), white);
+    assert_eq!(
+        pattern.at_point(point(0., 0.25, 0.)),
+        color(0.75, 0.75, 0.75)
+    );
+    assert_eq!(pattern.at_point(point(0., 0., 1.5)), color(0.5, 0.5, 0.5));
+    assert_eq!(pattern.at_point(point(0.6, 0., 0.8)), white);
+}
+
+#[test]
+fn ring_gradient_blends_between_bands() {
+    let white = color(1., 1., 1.);
+    let black = color(0., 0., 0.);
+    let pattern = ring_gradient_pattern(white, black);
+    assert_eq!(pattern.at_point(point(0., 0., 0.)), white);
+    assert_eq!(pattern.at_point(point(0.5, 0., 0.)), color(0.5, 0.5, 0.5));
+    assert_eq!(pattern.at_point(point(0., 0., 1.)), black);
+    assert_eq!(pattern.at_point(point(1.5, 0., 0.)), color(0.5, 0.5, 0.5));
+    assert_eq!(pattern.at_point(point(0., 0., -2.)), white);
+}
+
+#[test]
+fn checkers_alternate_per_axis_on_both_sides_of_the_origin() {
+    let white = color(1., 1., 1.);
+    let black = color(0., 0., 0.);
+    let pattern = checker_pattern(white, black);
+    assert_eq!(pattern.at_point(point(-0.5, 0.5, 0.5)), black);
+    assert_eq!(pattern.at_point(point(-0.5, -0.5, 0.5)), white);
+    assert_eq!(pattern.at_point(point(-0.5, -0.5, -0.5)), black);
+    assert_eq!(pattern.at_point(point(1.5, 0.5, 1.5)), white);
+}
+
+#[test]
+fn polka_dots_sit_in_the_middle_of_each_cell() {
+    let white = color(1., 1., 1.);
+    let black = color(0., 0., 0.);
+    let pattern = polka_dot_pattern(white, black);
+    assert_eq!(pattern.radius, 0.25);
+    assert_eq!(pattern.at_point(point(0.5, 0.5, 0.5)), white);
+    assert_eq!(pattern.at_point(point(-1.6, 2.4, 0.5)), white);
+    assert_eq!(pattern.at_point(point(0.05, 0.5, 0.5)), black);
+    assert_eq!(pattern.at_point(point(0., 0., 0.)), black);
+}
+
+#[test]
+fn hex_cells_tile_the_xz_plane() {
+    let s = 3_f64.sqrt();
+    assert_eq!(HexPattern::cell(point(0., 0., 0.)), (0, 0));
+    assert_eq!(HexPattern::cell(point(0.4, 0., 0.3)), (0, 0));
+    assert_eq!(HexPattern::cell(point(s, 0., 0.)), (1, 0));
+    assert_eq!(HexPattern::cell(point(s / 2., 0., 1.5)), (0, 1));
+    assert_eq!(HexPattern::cell(point(-s / 2., 7., 1.5)), (-1, 1));
+}
+
+#[test]
+fn neighbouring_hex_cells_never_match() {
+    let a = color(1., 0., 0.);
+    let b = color(0., 1., 0.);
+    let c = color(0., 0., 1.);
+    let pattern = hex_pattern(a, b, c);
+    let s = 3_f64.sqrt();
+    let center = pattern.at_point(point(0., 0., 0.));
+    assert_eq!(center, a);
+
+    let neighbours = [
+        point(s, 0., 0.),
+        point(-s, 0., 0.),
+        point(s / 2., 0., 1.5),
+        point(-s / 2., 0., 1.5),
+        point(s / 2., 0., -1.5),
+        point(-s / 2., 0., -1.5),
+    ];
+    for p in neighbours {
+        assert_ne!(pattern.at_point(p), center);
+    }
+}
@@ -0,0 +1,91 @@
+use std::f64::consts::PI;
+
+use ray_tracer::{
+    camera::{camera, view_transform, Camera},
+    picking::{pick, SecondaryKind},
+    tuple::{point, vector},
+    world::default_world,
+};
+
+fn camera_on_default_world() -> Camera {
+    let mut c = camera(11, 11, PI / 2.);
+    c.transform = view_transform(point(0., 0., -5.), point(0., 0., 0.), vector(0., 1., 0.));
+    return c;
+}
+
+#[test]
+fn picking_the_object_under_a_pixel() {
+    let w = default_world();
+    let picked = pick(&camera_on_default_world(), &w, 5, 5, false).unwrap();
+
+    let nearest = picked.nearest().unwrap();
+    assert_eq!(nearest.uuid, w.objects[0].uuid);
+    assert_eq!(nearest.t, 4.);
+    assert_eq!(nearest.point, point(0., 0., -1.));
+    assert_eq!(nearest.normal, vector(0., 0., -1.));
+    assert!(!nearest.inside);
+    assert!(picked.secondary.is_none());
+}
+
+#[test]
+fn picking_lists_every_hit_along_the_ray() {
+    let w = default_world();
+    let picked = pick(&camera_on_default_world(), &w, 5, 5, false).unwrap();
+
+    let ts: Vec<f64> = picked.hits.iter().map(|h| h.t).collect();
+    assert_eq!(ts, vec![4., 4.5, 5.5, 6.]);
+    assert_eq!(picked.hits[1].uuid, w.objects[1].uuid);
+    assert!(picked.hits[2].inside);
+    assert_eq!(picked.hits[2].normal, vector(0., 0., -1.));
+}
+
+#[test]
+fn picking_a_pixel_that_misses() {
+    let w = default_world();
+    let picked = pick(&camera_on_default_world(), &w, 0, 0, true).unwrap();
+    assert!(picked.nearest().is_none());
+    assert!(picked.secondary.unwrap().is_empty());
+}
+
+#[test]
+fn picking_outside_the_image_is_an_error() {
+    let w = default_world();
+    assert!(pick(&camera_on_default_world(), &w, 11, 5, false).is_err());
+    assert!(pick(&camera_on_default_world(), &w, 5, 11, false).is_err());
+}
+
+#[test]
+fn opaque_matte_surfaces_spawn_no_secondary_rays() {
+    let w = default_world();
+    let picked = pick(&camera_on_default_world(), &w, 5, 5, true).unwrap();
+    assert!(picked.secondary.unwrap().is_empty());
+}
+
+#[test]
+fn tracing_a_reflection() {
+    let mut w = default_world();
+    w.objects[0].material.reflective = 0.5;
+    let picked = pick(&camera_on_default_world(), &w, 5, 5, true).unwrap();
+
+    let secondary = picked.secondary.unwrap();
+    assert_eq!(secondary.len(), 1);
+    assert_eq!(secondary[0].kind, SecondaryKind::Reflection);
+    assert_eq!(secondary[0].ray.direction, vector(0., 0., -1.));
+    assert!(secondary[0].hit.is_none());
+    assert!(secondary[0].spawned.is_empty());
+}
+
+#[test]
+fn tracing_a_refraction_into_the_next_object() {
+    let mut w = default_world();
+    w.objects[0].material.transparency = 1.;
+    w.objects[0].material.refractive_index = 1.5;
+    let picked = pick(&camera_on_default_world(), &w, 5, 5, true).unwrap();
+
+    let secondary = picked.secondary.unwrap();
+    assert_eq!(secondary.len(), 1);
+    assert_eq!(secondary[0].kind, SecondaryKind::Refraction);
+    let hit = secondary[0].hit.as_ref().unwrap();
+    assert_eq!(hit.uuid, w.objects[1].uuid);
+    assert_eq!(hit.point, point(0., 0., -0.5));
+}
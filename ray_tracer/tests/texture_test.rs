@@ -0,0 +1,269 @@
+use ray_tracer::{
+    canvas::canvas,
+    color::{color, Color},
+    pattern::Pattern,
+    shapes::object::Object,
+    texture::*,
+    tuple::{point, Tuple},
+    utils::EPSILON,
+};
+
+fn assert_uv(actual: (f64, f64), expected: (f64, f64)) {
+    assert!(
+        (actual.0 - expected.0).abs() < EPSILON && (actual.1 - expected.1).abs() < EPSILON,
+        "{:?} != {:?}",
+        actual,
+        expected
+    );
+}
+
+fn align_check_face(main: Color) -> Box<dyn UvPattern> {
+    Box::new(uv_align_check(
+        main,
+        color(1., 0., 0.),
+        color(1., 1., 0.),
+        color(0., 1., 0.),
+        color(0., 1., 1.),
+    ))
+}
+
+#[test]
+fn checker_pattern_in_2d() {
+    let black = color(0., 0., 0.);
+    let white = color(1., 1., 1.);
+    let checkers = uv_checkers(2., 2., black, white);
+
+    assert_eq!(checkers.uv_pattern_at(0., 0.), black);
+    assert_eq!(checkers.uv_pattern_at(0.5, 0.), white);
+    assert_eq!(checkers.uv_pattern_at(0., 0.5), white);
+    assert_eq!(checkers.uv_pattern_at(0.5, 0.5), black);
+    assert_eq!(checkers.uv_pattern_at(1., 1.), black);
+}
+
+#[test]
+fn spherical_mapping_on_a_3d_point() {
+    let s = 2_f64.sqrt() / 2.;
+    let cases: Vec<(Tuple, (f64, f64))> = vec![
+        (point(0., 0., -1.), (0., 0.5)),
+        (point(1., 0., 0.), (0.25, 0.5)),
+        (point(0., 0., 1.), (0.5, 0.5)),
+        (point(-1., 0., 0.), (0.75, 0.5)),
+        (point(0., 1., 0.), (0.5, 1.)),
+        (point(0., -1., 0.), (0.5, 0.)),
+        (point(s, s, 0.), (0.25, 0.75)),
+    ];
+    for (p, expected) in cases {
+        assert_uv(spherical_map(p), expected);
+    }
+}
+
+#[test]
+fn planar_mapping_on_a_3d_point() {
+    let cases: Vec<(Tuple, (f64, f64))> = vec![
+        (point(0.25, 0., 0.5), (0.25, 0.5)),
+        (point(0.25, 0., -0.25), (0.25, 0.75)),
+        (point(0.25, 0.5, -0.25), (0.25, 0.75)),
+        (point(1.25, 0., 0.5), (0.25, 0.5)),
+        (point(0.25, 0., -1.75), (0.25, 0.25)),
+        (point(1., 0., -1.), (0., 0.)),
+        (point(0., 0., 0.), (0., 0.)),
+    ];
+    for (p, expected) in cases {
+        assert_uv(planar_map(p), expected);
+    }
+}
+
+#[test]
+fn cylindrical_mapping_on_a_3d_point() {
+    let s = 2_f64.sqrt() / 2.;
+    let cases: Vec<(Tuple, (f64, f64))> = vec![
+        (point(0., 0., -1.), (0., 0.)),
+        (point(0., 0.5, -1.), (0., 0.5)),
+        (point(0., 1., -1.), (0., 0.)),
+        (point(s, 0.5, -s), (0.125, 0.5)),
+        (point(1., 0.5, 0.), (0.25, 0.5)),
+        (point(s, 0.5, s), (0.375, 0.5)),
+        (point(0., -0.25, 1.), (0.5, 0.75)),
+        (point(-s, 0.5, s), (0.625, 0.5)),
+        (point(-1., 1.25, 0.), (0.75, 0.25)),
+        (point(-s, 0.5, -s), (0.875, 0.5)),
+    ];
+    for (p, expected) in cases {
+        assert_uv(cylindrical_map(p), expected);
+    }
+}
+
+#[test]
+fn layout_of_the_align_check_pattern() {
+    let main = color(1., 1., 1.);
+    let pattern = align_check_face(main);
+
+    assert_eq!(pattern.uv_pattern_at(0.5, 0.5), main);
+    assert_eq!(pattern.uv_pattern_at(0.1, 0.9), color(1., 0., 0.));
+    assert_eq!(pattern.uv_pattern_at(0.9, 0.9), color(1., 1., 0.));
+    assert_eq!(pattern.uv_pattern_at(0.1, 0.1), color(0., 1., 0.));
+    assert_eq!(pattern.uv_pattern_at(0.9, 0.1), color(0., 1., 1.));
+}
+
+#[test]
+fn identifying_the_face_of_a_cube_from_a_point() {
+    assert_eq!(face_from_point(point(-1., 0.5, -0.25)), CubeFace::Left);
+    assert_eq!(face_from_point(point(1.1, -0.75, 0.8)), CubeFace::Right);
+    assert_eq!(face_from_point(point(0.1, 0.6, 0.9)), CubeFace::Front);
+    assert_eq!(face_from_point(point(-0.7, 0., -2.)), CubeFace::Back);
+    assert_eq!(face_from_point(point(0.5, 1., 0.9)), CubeFace::Up);
+    assert_eq!(face_from_point(point(-0.2, -1.3, 1.1)), CubeFace::Down);
+}
+
+#[test]
+fn uv_mapping_each_face_of_a_cube() {
+    let cases = vec![
+        (CubeFace::Front, point(-0.5, 0.5, 1.), (0.25, 0.75)),
+        (CubeFace::Front, point(0.5, -0.5, 1.), (0.75, 0.25)),
+        (CubeFace::Back, point(0.5, 0.5, -1.), (0.25, 0.75)),
+        (CubeFace::Back, point(-0.5, -0.5, -1.), (0.75, 0.25)),
+        (CubeFace::Left, point(-1., 0.5, -0.5), (0.25, 0.75)),
+        (CubeFace::Left, point(-1., -0.5, 0.5), (0.75, 0.25)),
+        (CubeFace::Right, point(1., 0.5, 0.5), (0.25, 0.75)),
+        (CubeFace::Right, point(1., -0.5, -0.5), (0.75, 0.25)),
+        (CubeFace::Up, point(-0.5, 1., -0.5), (0.25, 0.75)),
+        (CubeFace::Up, point(0.5, 1., 0.5), (0.75, 0.25)),
+        (CubeFace::Down, point(-0.5, -1., 0.5), (0.25, 0.75)),
+        (CubeFace::Down, point(0.5, -1., -0.5), (0.75, 0.25)),
+    ];
+    for (face, p, expected) in cases {
+        assert_uv(cube_uv(face, p), expected);
+    }
+}
+
+#[test]
+fn texture_map_pattern_with_spherical_map() {
+    let black = color(0., 0., 0.);
+    let white = color(1., 1., 1.);
+    let pattern = texture_map(
+        UvMapping::Spherical,
+        Box::new(uv_checkers(16., 8., black, white)),
+    );
+
+    let cases = vec![
+        (point(0.4315, 0.4670, 0.7719), white),
+        (point(-0.9654, 0.2552, -0.0534), black),
+        (point(0.1039, 0.7090, 0.6975), white),
+        (point(-0.4986, -0.7856, -0.3663), black),
+        (point(-0.0317, -0.9395, 0.3411), black),
+        (point(0.4809, -0.7721, 0.4154), black),
+        (point(0.0285, -0.9612, -0.2745), black),
+        (point(-0.5734, -0.2162, -0.7903), white),
+        (point(0.7688, -0.1470, 0.6223), black),
+        (point(-0.7652, 0.2175, 0.6060), black),
+    ];
+    for (p, expected) in cases {
+        assert_eq!(pattern.at_point(p), expected);
+    }
+}
+
+#[test]
+fn cube_map_finds_colors_on_every_face() {
+    let red = color(1., 0., 0.);
+    let yellow = color(1., 1., 0.);
+    let brown = color(1., 0.5, 0.);
+    let green = color(0., 1., 0.);
+    let cyan = color(0., 1., 1.);
+    let blue = color(0., 0., 1.);
+    let purple = color(1., 0., 1.);
+    let white = color(1., 1., 1.);
+
+    let face = |main, ul, ur, bl, br| -> Box<dyn UvPattern> {
+        Box::new(uv_align_check(main, ul, ur, bl, br))
+    };
+    let pattern = cube_map(
+        face(yellow, cyan, red, blue, brown),
+        face(cyan, red, yellow, brown, green),
+        face(red, yellow, purple, green, white),
+        face(green, purple, cyan, white, blue),
+        face(brown, cyan, purple, red, yellow),
+        face(purple, brown, green, blue, white),
+    );
+
+    let object = Object::new_sphere();
+    let cases = vec![
+        (point(-1., 0., 0.), yellow),
+        (point(-1., 0.9, -0.9), cyan),
+        (point(-1., 0.9, 0.9), red),
+        (point(-1., -0.9, -0.9), blue),
+        (point(-1., -0.9, 0.9), brown),
+        (point(0., 0., 1.), cyan),
+        (point(-0.9, 0.9, 1.), red),
+        (point(0.9, 0.9, 1.), yellow),
+        (point(-0.9, -0.9, 1.), brown),
+        (point(0.9, -0.9, 1.), green),
+        (point(1., 0., 0.), red),
+        (point(1., 0.9, 0.9), yellow),
+        (point(1., 0.9, -0.9), purple),
+        (point(0., 0., -1.), green),
+        (point(0.9, 0.9, -1.), purple),
+        (point(0., 1., 0.), brown),
+        (point(-0.9, 1., -0.9), cyan),
+        (point(0.9, 1., 0.9), yellow),
+        (point(0., -1., 0.), purple),
+        (point(-0.9, -1., 0.9), brown),
+        (point(0.9, -1., -0.9), white),
+    ];
+    for (p, expected) in cases {
+        assert_eq!(pattern.at_object(&object, p), expected);
+    }
+}
+
+fn two_by_two_image() -> ImageTexture {
+    let mut c = canvas(2, 2);
+    c.write_pixel(0, 0, color(1., 0., 0.)).unwrap();
+    c.write_pixel(1, 0, color(0., 1., 0.)).unwrap();
+    c.write_pixel(0, 1, color(0., 0., 1.)).unwrap();
+    c.write_pixel(1, 1, color(1., 1., 1.)).unwrap();
+    image_texture(c).unwrap()
+}
+
+#[test]
+fn empty_images_are_not_textures() {
+    assert!(image_texture(canvas(0, 0)).is_err());
+    assert!(image_texture(canvas(4, 0)).is_err());
+}
+
+#[test]
+fn image_texture_puts_the_image_bottom_row_at_v_zero() {
+    let texture = two_by_two_image();
+
+    assert_eq!(texture.uv_pattern_at(0.25, 0.75), color(1., 0., 0.));
+    assert_eq!(texture.uv_pattern_at(0.75, 0.75), color(0., 1., 0.));
+    assert_eq!(texture.uv_pattern_at(0.25, 0.25), color(0., 0., 1.));
+    assert_eq!(texture.uv_pattern_at(0.75, 0.25), color(1., 1., 1.));
+}
+
+#[test]
+fn image_texture_wraps_or_clamps_outside_the_unit_square() {
+    let mut texture = two_by_two_image();
+    assert_eq!(texture.uv_pattern_at(1.25, 0.75), color(1., 0., 0.));
+
+    texture.wrap = Wrap::Clamp;
+    assert_eq!(texture.uv_pattern_at(1.25, 0.75), color(0., 1., 0.));
+    assert_eq!(texture.uv_pattern_at(-3., -3.), color(0., 0., 1.));
+}
+
+#[test]
+fn bilinear_filtering_blends_neighbouring_texels() {
+    let mut texture = two_by_two_image();
+    texture.filter = Filter::Bilinear;
+    texture.wrap = Wrap::Clamp;
+
+    assert_eq!(texture.uv_pattern_at(0.25, 0.75), color(1., 0., 0.));
+    assert_eq!(texture.uv_pattern_at(0.5, 0.75), color(0.5, 0.5, 0.));
+    assert_eq!(texture.uv_pattern_at(0.5, 0.5), color(0.5, 0.5, 0.5));
+}
+
+#[test]
+fn image_texture_works_through_a_uv_mapping() {
+    let pattern = texture_map(UvMapping::Planar, Box::new(two_by_two_image()));
+
+    assert_eq!(pattern.at_point(point(0.25, 0., 0.75)), color(1., 0., 0.));
+    assert_eq!(pattern.at_point(point(1.75, 0., 0.25)), color(1., 1., 1.));
+}
@@ -0,0 +1,79 @@
+use ray_tracer::{
+    canvas::{canvas, ImageType},
+    color::color,
+    tonemap::*,
+};
+
+#[test]
+fn exposure_scales_by_powers_of_two() {
+    let c = color(0.25, 0.5, 1.);
+
+    assert_eq!(ToneMap::Exposure(1.).map(c), color(0.5, 1., 2.));
+    assert_eq!(ToneMap::Exposure(-2.).map(c), color(0.0625, 0.125, 0.25));
+    assert_eq!(ToneMap::Exposure(0.).map(c), c);
+}
+
+#[test]
+fn clamp_limits_channels_to_0_to_1() {
+    assert_eq!(ToneMap::Clamp.map(color(-0.5, 0.5, 4.)), color(0., 0.5, 1.));
+}
+
+#[test]
+fn reinhard_compresses_luminance_and_keeps_hue() {
+    let white = ToneMap::Reinhard.map(color(1., 1., 1.));
+    assert_eq!(white, color(0.5, 0.5, 0.5));
+
+    let bright = ToneMap::Reinhard.map(color(30., 15., 3.));
+    assert!(luminance(bright) < 1.);
+    assert!((bright.r / bright.g - 2.).abs() < 1e-9);
+    assert!((bright.g / bright.b - 5.).abs() < 1e-9);
+
+    assert_eq!(ToneMap::Reinhard.map(color(0., 0., 0.)), color(0., 0., 0.));
+}
+
+#[test]
+fn aces_filmic_rolls_off_highlights() {
+    let aces = |v: f64| ToneMap::AcesFilmic.map(color(v, v, v)).r;
+
+    assert_eq!(aces(0.), 0.);
+    assert!((aces(0.18) - 0.2669).abs() < 0.0001);
+    assert!(aces(1.) < aces(2.) && aces(2.) < aces(8.));
+    assert_eq!(aces(100.), 1.);
+}
+
+#[test]
+fn srgb_transfer_function() {
+    assert_eq!(srgb_encode(0.), 0.);
+    assert!((srgb_encode(1.) - 1.).abs() < 1e-12);
+    assert!((srgb_encode(0.002) - 0.02584).abs() < 1e-12);
+    assert!((srgb_encode(0.5) - 0.735357).abs() < 0.000001);
+    assert_eq!(srgb_encode(2.), srgb_encode(1.));
+
+    for v in [0.001, 0.04, 0.2, 0.5, 0.9] {
+        assert!((srgb_decode(srgb_encode(v)) - v).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn tone_mapping_a_canvas_applies_operators_in_order() {
+    let mut c = canvas(2, 1);
+    c.write_pixel(0, 0, color(1., 1., 1.)).unwrap();
+    c.write_pixel(1, 0, color(0.5, 3., 0.)).unwrap();
+
+    c.tone_map(&[ToneMap::Exposure(1.), ToneMap::Clamp]);
+
+    assert_eq!(c.pixel_at(0, 0), color(1., 1., 1.));
+    assert_eq!(c.pixel_at(1, 0), color(1., 1., 0.));
+}
+
+#[test]
+fn hdr_formats_are_written_linear() {
+    let mut c = canvas(1, 1);
+    c.write_pixel(0, 0, color(0.5, 2., 0.)).unwrap();
+    let mut pfm = vec![];
+    c.write(&mut pfm, ImageType::PFM).unwrap();
+
+    let raster = &pfm[pfm.len() - 12..];
+    assert_eq!(&raster[..4], &0.5f32.to_le_bytes());
+    assert_eq!(&raster[4..8], &2f32.to_le_bytes());
+}
@@ -1,11 +1,15 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
 use ray_tracer::{
     color::color,
     light::point_light,
-    material::Material,
-    pattern::test_pattern,
+    material::{microfacet_material, Dispersion, Material},
+    microfacet::fresnel_schlick,
+    pattern::{solid_pattern, stripe_pattern, test_pattern},
     ray::*,
     shapes::object::*,
-    transforms::{scaling, translation},
+    transforms::{rotation_x, scaling, translation},
     tuple::{point, vector},
     utils::EPSILON,
     world::{default_world, *},
@@ -146,14 +150,14 @@ fn color_at_when_intersection_behind_ray() {
     obj1.set_material(Material {
         ambient: 1.,
         pattern: None,
-        ..obj1.material
+        ..obj1.material.clone()
     });
 
     let obj2 = &mut w.objects[1];
     obj2.set_material(Material {
         ambient: 1.,
         pattern: None,
-        ..obj2.material
+        ..obj2.material.clone()
     });
 
     let r = ray(point(0., 0., 0.75), vector(0., 0., -1.));
@@ -266,6 +270,35 @@ fn reflected_color_for_reflective_material() {
     );
 }
 
+#[test]
+fn reflective_pattern_mirrors_only_some_stripes() {
+    let square_root_of_2 = 2_f64.sqrt();
+    let mut w = default_world();
+
+    let mut shape = Object::new_plane();
+    shape.material.reflective_pattern = Some(Arc::new(stripe_pattern(
+        color(0.5, 0.5, 0.5),
+        color(0., 0., 0.),
+    )));
+    shape.transform = translation(0., -1., 0.);
+    w.objects.push(shape);
+
+    let direction = vector(0., -square_root_of_2 / 2., square_root_of_2 / 2.);
+    let reflected_from = |origin| {
+        let r = ray(origin, direction);
+        let i = intersection(square_root_of_2, w.objects.last().unwrap());
+        let xs = Intersect { locations: vec![i] };
+        let comps = prepare_computations(&xs.locations[0], &r, &xs);
+        w.reflected_color(&comps, 1)
+    };
+
+    assert_eq!(
+        reflected_from(point(0., 0., -3.)),
+        color(0.19033, 0.23791, 0.14274)
+    );
+    assert_eq!(reflected_from(point(1., 0., -3.)), color(0., 0., 0.));
+}
+
 #[test]
 fn shade_hit_with_reflective_material() {
     let square_root_of_2 = (2. as f64).sqrt();
@@ -291,6 +324,209 @@ fn shade_hit_with_reflective_material() {
     assert_eq!(w.shade_hit(&comps, 5), color(0.87676, 0.92434, 0.82917));
 }
 
+#[test]
+fn smooth_metal_reflects_tinted_by_its_base_color() {
+    let square_root_of_2 = 2_f64.sqrt();
+    let mut w = default_world();
+
+    let gold = color(1., 0.78, 0.34);
+    let mut shape = Object::new_plane();
+    shape.material = microfacet_material(gold, 1., 0., 1.5);
+    shape.transform = translation(0., -1., 0.);
+    w.objects.push(shape);
+
+    let r = ray(
+        point(0., 0., -3.),
+        vector(0., -square_root_of_2 / 2., square_root_of_2 / 2.),
+    );
+    let i = intersection(square_root_of_2, w.objects.last().unwrap());
+    let xs = Intersect { locations: vec![i] };
+    let comps = prepare_computations(&xs.locations[0], &r, &xs);
+
+    let mirrored = w.color_at(&ray(comps.over_point, comps.reflectv), 0);
+    let cos = comps.eyev.dot(comps.normalv);
+    let expected = mirrored * fresnel_schlick(cos, gold);
+
+    assert_eq!(w.reflected_color(&comps, 1), expected);
+}
+
+#[test]
+fn a_reflective_pattern_masks_microfacet_reflections() {
+    let square_root_of_2 = 2_f64.sqrt();
+    let mut w = default_world();
+
+    let gold = color(1., 0.78, 0.34);
+    let mut shape = Object::new_plane();
+    shape.material = microfacet_material(gold, 1., 0., 1.5);
+    shape.material.reflective_pattern = Some(Arc::new(solid_pattern(color(0.5, 0.5, 0.5))));
+    shape.transform = translation(0., -1., 0.);
+    w.objects.push(shape);
+
+    let r = ray(
+        point(0., 0., -3.),
+        vector(0., -square_root_of_2 / 2., square_root_of_2 / 2.),
+    );
+    let i = intersection(square_root_of_2, w.objects.last().unwrap());
+    let xs = Intersect { locations: vec![i] };
+    let comps = prepare_computations(&xs.locations[0], &r, &xs);
+
+    let mirrored = w.color_at(&ray(comps.over_point, comps.reflectv), 0);
+    let cos = comps.eyev.dot(comps.normalv);
+    let expected = mirrored * fresnel_schlick(cos, gold) * 0.5;
+
+    assert_eq!(w.reflected_color(&comps, 1), expected);
+}
+
+#[test]
+fn rough_metal_reflections_are_reproducible() {
+    let mut w = default_world();
+    let mut shape = Object::new_plane();
+    shape.material = microfacet_material(color(1., 0.78, 0.34), 1., 0.6, 1.5);
+    shape.material.glossy_samples = 8;
+    shape.transform = translation(0., -1., 0.);
+    w.objects.push(shape);
+
+    let r = ray(point(0., 0., -3.), vector(0., -1., 1.).normalize());
+    let reflect = |seed: u64| {
+        let xs = w.intersect(&r);
+        let hit = xs.hit().unwrap();
+        let comps = prepare_computations(&hit, &r, &xs);
+        (w.reflected_color(&comps, 5), w.color_at_seeded(&r, 5, seed))
+    };
+
+    let (reflected, shaded) = reflect(7);
+    assert_eq!(reflect(7), (reflected, shaded));
+    assert_ne!(reflect(8).1, shaded);
+}
+
+#[test]
+fn a_roughness_pattern_smooths_microfacet_reflections() {
+    let gold = color(1., 0.78, 0.34);
+    let mut w = default_world();
+    let mut shape = Object::new_plane();
+    shape.material = microfacet_material(gold, 1., 0., 1.5);
+    shape.transform = translation(0., -1., 0.);
+    w.objects.push(shape);
+
+    let r = ray(point(0., 0., -3.), vector(0., -1., 1.).normalize());
+    let reflect = |w: &World| {
+        let xs = w.intersect(&r);
+        let hit = xs.hit().unwrap();
+        let comps = prepare_computations(&hit, &r, &xs);
+        w.reflected_color(&comps, 5)
+    };
+    let mirrored = reflect(&w);
+
+    let rough = w.objects.last_mut().unwrap();
+    rough.material = microfacet_material(gold, 1., 0.6, 1.5);
+    rough.material.roughness_pattern = Some(Arc::new(solid_pattern(color(0., 0., 0.))));
+
+    assert_eq!(reflect(&w), mirrored);
+}
+
+fn uniformly_lit_surroundings() -> Object {
+    let mut sky = Object::new_sphere();
+    sky.set_transform(scaling(20., 20., 20.));
+    sky.material.ambient = 1.;
+    sky.material.diffuse = 0.;
+    sky.material.specular = 0.;
+    sky
+}
+
+#[test]
+fn glossy_reflection_of_uniform_surroundings_matches_mirror() {
+    let square_root_of_2 = 2_f64.sqrt();
+    let mut w = default_world();
+    w.objects = vec![uniformly_lit_surroundings()];
+
+    let mut shape = Object::new_plane();
+    shape.material.reflective = 0.5;
+    shape.material.roughness = 0.4;
+    shape.transform = translation(0., -1., 0.);
+    w.objects.push(shape);
+
+    let r = ray(
+        point(0., 0., -3.),
+        vector(0., -square_root_of_2 / 2., square_root_of_2 / 2.),
+    );
+    let i = intersection(square_root_of_2, w.objects.last().unwrap());
+    let xs = Intersect { locations: vec![i] };
+    let comps = prepare_computations(&xs.locations[0], &r, &xs);
+
+    assert_eq!(w.reflected_color(&comps, 5), color(0.5, 0.5, 0.5));
+}
+
+#[test]
+fn glossy_reflection_blurs_a_small_target() {
+    let mut target = Object::new_sphere();
+    target.set_transform(translation(0., 2., 2.) * scaling(0.3, 0.3, 0.3));
+    target.material.ambient = 1.;
+    target.material.diffuse = 0.;
+    target.material.specular = 0.;
+
+    let mut mirror = Object::new_plane();
+    mirror.material.reflective = 1.;
+    mirror.material.glossy_samples = 64;
+
+    let mut w = default_world();
+    w.objects = vec![target, mirror];
+
+    let r = ray(point(0., 2., -2.), vector(0., -1., 1.).normalize());
+    let reflect = |w: &World, remaining: u8| {
+        let xs = w.intersect(&r);
+        let comps = prepare_computations(&xs.locations[0], &r, &xs);
+        w.reflected_color(&comps, remaining)
+    };
+
+    assert_eq!(reflect(&w, 5), color(1., 1., 1.));
+
+    w.objects[1].material.roughness = 0.8;
+    let blurred = reflect(&w, 5);
+    assert!(blurred.r > 0. && blurred.r < 1.);
+
+    // camera rays are multi-sampled however deep the caller starts
+    let shallow = reflect(&w, 2);
+    assert!(shallow.r > 0. && shallow.r < 1.);
+}
+
+#[test]
+fn frosted_refraction_of_uniform_surroundings_matches_clear_glass() {
+    let mut w = default_world();
+    w.objects = vec![uniformly_lit_surroundings(), Object::new_glass_sphere()];
+
+    let r = ray(point(0., 0., -5.), vector(0., 0., 1.));
+    let refract = |w: &World| {
+        let xs = w.intersect(&r);
+        let hit = xs.hit().unwrap();
+        let comps = prepare_computations(&hit, &r, &xs);
+        w.refracted_color(&comps, 5)
+    };
+
+    let clear = refract(&w);
+    w.objects[1].material.roughness = 0.3;
+
+    assert_eq!(refract(&w), clear);
+}
+
+#[test]
+fn clear_microfacet_surfaces_do_not_add_light_to_uniform_surroundings() {
+    let mut clear = Object::new_sphere();
+    clear.material = microfacet_material(color(1., 1., 1.), 0., 0., 1.5);
+    clear.material.ambient = 0.;
+    clear.material.transparency = 1.;
+
+    let mut w = default_world();
+    // outside the surroundings, so only their ambient light is seen
+    w.light = point_light(point(0., 0., -30.), color(1., 1., 1.));
+    w.objects = vec![uniformly_lit_surroundings(), clear];
+
+    for y in [0., 0.5, 0.99] {
+        let c = w.color_at(&ray(point(0., y, -5.), vector(0., 0., 1.)), 5);
+        assert!(c.r <= 1. + EPSILON, "{}: {:?}", y, c);
+        assert!(c.r > 0.5, "{}: {:?}", y, c);
+    }
+}
+
 #[test]
 fn color_at_with_mutually_reflective_surfaces() {
     let mut w = default_world();
@@ -392,7 +628,7 @@ fn refracted_color_with_refracted_ray() {
     {
         let a = &mut w.objects[0];
         a.material.ambient = 1.0;
-        a.material.pattern = Some(Box::new(test_pattern()));
+        a.material.pattern = Some(Arc::new(test_pattern()));
     }
 
     {
@@ -414,6 +650,86 @@ fn refracted_color_with_refracted_ray() {
     assert_eq!(c, color(0., 0.99888, 0.04722))
 }
 
+#[test]
+fn refracted_color_is_attenuated_by_distance_through_absorbing_material() {
+    let mut backdrop = Object::new_plane();
+    backdrop.set_transform(translation(0., 0., 5.) * rotation_x(PI / 2.));
+    backdrop.material.ambient = 1.;
+
+    // without internal reflections the ray crosses the sphere only once
+    let mut glass = Object::new_glass_sphere();
+    glass.material.reflective = 0.;
+    let mut w = default_world();
+    w.objects = vec![glass, backdrop];
+
+    let r = ray(point(0., 0., -5.), vector(0., 0., 1.));
+    let clear = {
+        let xs = w.intersect(&r);
+        let comps = prepare_computations(&xs.locations[0], &r, &xs);
+        w.refracted_color(&comps, 5)
+    };
+
+    w.objects[0].material.absorption = color(0.5, 0.1, 0.);
+    let xs = w.intersect(&r);
+    let comps = prepare_computations(&xs.locations[0], &r, &xs);
+    let tinted = w.refracted_color(&comps, 5);
+
+    let expected = clear * w.objects[0].material.attenuation(2.);
+    assert_eq!(tinted, expected);
+    assert!(tinted.r < clear.r && tinted.g < clear.g);
+    assert_eq!(tinted.b, clear.b);
+}
+
+#[test]
+fn reflections_inside_absorbing_material_are_attenuated() {
+    let mut w = default_world();
+    let mut glass = Object::new_glass_sphere();
+    glass.material.reflective = 1.;
+    glass.material.ambient = 1.;
+    w.objects = vec![glass];
+
+    let r = ray(point(0., 0., 0.), vector(0., 0., 1.));
+    let reflected = |w: &World| {
+        let xs = w.intersect(&r);
+        let comps = prepare_computations(&xs.locations[1], &r, &xs);
+        w.reflected_color(&comps, 1)
+    };
+    let clear = reflected(&w);
+
+    w.objects[0].material.absorption = color(0.5, 0.1, 0.);
+    let tinted = reflected(&w);
+
+    // the reflection crosses the sphere once before its next hit
+    assert_eq!(tinted, clear * w.objects[0].material.attenuation(2.));
+    assert!(tinted.r < clear.r);
+}
+
+#[test]
+fn thick_absorbing_glass_is_darker_than_thin_glass() {
+    let mut backdrop = Object::new_plane();
+    backdrop.set_transform(translation(0., 0., 5.) * rotation_x(PI / 2.));
+    backdrop.material.ambient = 1.;
+
+    let mut thin = Object::new_glass_sphere();
+    thin.material.absorption = color(0.8, 0.2, 0.);
+    thin.set_transform(scaling(1., 1., 0.25));
+    let mut thick = Object::new_glass_sphere();
+    thick.material.absorption = color(0.8, 0.2, 0.);
+
+    let r = ray(point(0., 0., -5.), vector(0., 0., 1.));
+    let mut w = default_world();
+
+    w.objects = vec![thin, backdrop];
+    let thin_color = w.color_at(&r, 5);
+    let backdrop = w.objects.pop().unwrap();
+
+    w.objects = vec![thick, backdrop];
+    let thick_color = w.color_at(&r, 5);
+
+    assert!(thick_color.r < thin_color.r);
+    assert!(thick_color.r / thick_color.b < thin_color.r / thin_color.b);
+}
+
 #[test]
 fn shade_hit_with_transparent_material() {
     let square_root_of_2 = (2. as f64).sqrt();
@@ -479,6 +795,91 @@ fn schlick_approximation_with_small_angle_and_n2_greater_than_n1() {
     assert!((reflectance - 0.48873).abs() < EPSILON)
 }
 
+#[test]
+fn prepare_computations_records_objects_on_both_sides() {
+    let a = Object::new_glass_sphere();
+    let mut b = Object::new_glass_sphere();
+    b.set_transform(scaling(0.5, 0.5, 0.5));
+
+    let r = ray(point(0., 0., -4.), vector(0., 0., 1.));
+    let xs = intersections(vec![
+        intersection(3., &a),
+        intersection(3.5, &b),
+        intersection(4.5, &b),
+        intersection(5., &a),
+    ]);
+
+    let entering = prepare_computations(&xs.locations[0], &r, &xs);
+    assert!(entering.n1_object.is_none());
+    assert!(entering.n2_object.unwrap() == &a);
+
+    let nested = prepare_computations(&xs.locations[1], &r, &xs);
+    assert!(nested.n1_object.unwrap() == &a);
+    assert!(nested.n2_object.unwrap() == &b);
+}
+
+#[test]
+fn schlick_color_is_grey_without_dispersion() {
+    let shape = Object::new_glass_sphere();
+    let r = ray(point(0., 0.99, -2.), vector(0., 0., 1.));
+    let xs = intersections(vec![intersection(1.8589, &shape)]);
+    let comps = prepare_computations(&xs.locations[0], &r, &xs);
+    let reflectance = schlick(&comps);
+
+    assert_eq!(
+        schlick_color(&comps),
+        color(reflectance, reflectance, reflectance)
+    );
+}
+
+#[test]
+fn schlick_color_varies_per_channel_with_dispersion() {
+    let mut shape = Object::new_glass_sphere();
+    shape.material.dispersion = Dispersion::PerChannel(1.4, 1.5, 1.6);
+    let r = ray(point(0., 0., -2.), vector(0., 0., 1.));
+    let xs = intersections(vec![intersection(1., &shape), intersection(3., &shape)]);
+    let comps = prepare_computations(&xs.locations[0], &r, &xs);
+    let reflectance = schlick_color(&comps);
+
+    assert!(reflectance.r < reflectance.g && reflectance.g < reflectance.b);
+    assert!((reflectance.g - 0.04).abs() < EPSILON);
+}
+
+#[test]
+fn dispersive_refraction_bends_channels_apart() {
+    let mut w = default_world();
+    {
+        let a = &mut w.objects[0];
+        a.material.ambient = 1.0;
+        a.material.pattern = Some(Arc::new(test_pattern()));
+    }
+
+    let r = ray(point(0., 0., 0.1), vector(0., 1., 0.));
+    let refract = |w: &World| {
+        let xs = intersections(vec![
+            intersection(-0.9899, &w.objects[0]),
+            intersection(-0.4899, &w.objects[1]),
+            intersection(0.4899, &w.objects[1]),
+            intersection(0.9899, &w.objects[0]),
+        ]);
+        let comps = prepare_computations(&xs.locations[2], &r, &xs);
+        w.refracted_color(&comps, 5)
+    };
+
+    {
+        let b = &mut w.objects[1];
+        b.material.transparency = 1.0;
+        b.material.refractive_index = 1.5;
+        b.material.dispersion = Dispersion::PerChannel(1.5, 1.5, 1.5);
+    }
+    assert_eq!(refract(&w), color(0., 0.99888, 0.04722));
+
+    w.objects[1].material.dispersion = Dispersion::PerChannel(1.45, 1.5, 1.55);
+    let dispersed = refract(&w);
+    assert!((dispersed.g - 0.99888).abs() < EPSILON);
+    assert!((dispersed.b - 0.04722).abs() > EPSILON);
+}
+
 #[test]
 fn shade_hit_with_a_reflective_and_transparent_material() {
     let square_root_of_2 = (2. as f64).sqrt();
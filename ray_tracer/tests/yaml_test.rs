@@ -0,0 +1,328 @@
+use std::f64::consts::PI;
+
+use ray_tracer::{
+    camera::view_transform,
+    color::color,
+    scene::{Scene, SceneError},
+    transforms::{rotation_x, scaling, translation},
+    tuple::{point, vector},
+    yaml::*,
+};
+
+const CAMERA_AND_LIGHT: &str = "
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 0.785
+  from: [0, 1.5, -5]
+  to: [0, 1, 0]
+  up: [0, 1, 0]
+
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 1, 1]
+";
+
+fn scene_with(items: &str) -> Result<Scene, SceneError> {
+    parse_scene(&format!("{}{}", CAMERA_AND_LIGHT, items))
+}
+
+#[test]
+fn loading_camera_and_light() {
+    let scene = scene_with("").unwrap();
+
+    assert_eq!(scene.camera.hsize, 100);
+    assert_eq!(scene.camera.vsize, 50);
+    assert_eq!(scene.camera.field_of_view, 0.785);
+    assert_eq!(
+        scene.camera.transform,
+        view_transform(point(0., 1.5, -5.), point(0., 1., 0.), vector(0., 1., 0.))
+    );
+    assert_eq!(scene.world.light.position, point(-10., 10., -10.));
+    assert_eq!(scene.world.light.intensity, color(1., 1., 1.));
+    assert!(scene.world.objects.is_empty());
+}
+
+#[test]
+fn adding_shapes_with_materials_and_transforms() {
+    let scene = scene_with(
+        "
+- add: plane
+  material:
+    color: [1, 0.9, 0.9]
+    specular: 0
+  transform:
+    - [rotate-x, 1.5707963267948966]
+    - [translate, 0, 0, 5]
+
+- add: sphere
+  material:
+    reflective: 0.5
+    refractive-index: 1.5
+",
+    )
+    .unwrap();
+    let objects = &scene.world.objects;
+
+    assert_eq!(objects.len(), 2);
+    assert_eq!(objects[0].material.color, color(1., 0.9, 0.9));
+    assert_eq!(objects[0].material.specular, 0.);
+    assert_eq!(
+        objects[0].transform,
+        translation(0., 0., 5.) * rotation_x(PI / 2.)
+    );
+    assert_eq!(objects[1].material.reflective, 0.5);
+    assert_eq!(objects[1].material.refractive_index, 1.5);
+}
+
+#[test]
+fn defined_materials_can_be_extended() {
+    let scene = scene_with(
+        "
+- define: white-material
+  value:
+    color: [1, 1, 1]
+    diffuse: 0.7
+    reflective: 0.1
+
+- define: blue-material
+  extend: white-material
+  value:
+    color: [0.537, 0.831, 0.914]
+
+- add: sphere
+  material: blue-material
+",
+    )
+    .unwrap();
+    let material = &scene.world.objects[0].material;
+
+    assert_eq!(material.color, color(0.537, 0.831, 0.914));
+    assert_eq!(material.diffuse, 0.7);
+    assert_eq!(material.reflective, 0.1);
+}
+
+#[test]
+fn defined_transforms_expand_in_place() {
+    let scene = scene_with(
+        "
+- define: standard-transform
+  value:
+    - [translate, 1, -1, 1]
+    - [scale, 0.5, 0.5, 0.5]
+
+- define: large-object
+  value:
+    - standard-transform
+    - [scale, 3.5, 3.5, 3.5]
+
+- add: sphere
+  transform:
+    - large-object
+    - [translate, 8.5, 1.5, -0.5]
+",
+    )
+    .unwrap();
+
+    assert_eq!(
+        scene.world.objects[0].transform,
+        translation(8.5, 1.5, -0.5)
+            * scaling(3.5, 3.5, 3.5)
+            * scaling(0.5, 0.5, 0.5)
+            * translation(1., -1., 1.)
+    );
+}
+
+#[test]
+fn materials_can_name_library_presets() {
+    let scene = scene_with(
+        "
+- add: sphere
+  material: diamond
+",
+    )
+    .unwrap();
+
+    assert_eq!(scene.world.objects[0].material.refractive_index, 2.417);
+}
+
+#[test]
+fn patterns_can_be_nested() {
+    let scene = scene_with(
+        "
+- add: plane
+  material:
+    pattern:
+      type: stripes
+      colors:
+        - type: checkers
+          colors: [[1, 1, 1], [0, 0, 0]]
+          transform:
+            - [scale, 0.25, 0.25, 0.25]
+        - [1, 0, 0]
+",
+    )
+    .unwrap();
+    let object = &scene.world.objects[0];
+    let at = |x, z| object.material.color_at(object, point(x, 0.01, z));
+
+    assert_eq!(at(0.1, 0.1), color(1., 1., 1.));
+    assert_eq!(at(0.1, 0.35), color(0., 0., 0.));
+    assert_eq!(at(1.5, 0.1), color(1., 0., 0.));
+}
+
+#[test]
+fn missing_camera_is_reported() {
+    let error = parse_scene(
+        "
+- add: light
+  at: [0, 0, 0]
+  intensity: [1, 1, 1]
+",
+    )
+    .err()
+    .unwrap();
+
+    assert_eq!(error.line, None);
+    assert_eq!(error.to_string(), "scene has no camera");
+}
+
+#[test]
+fn errors_carry_line_numbers() {
+    let cases = [
+        ("\n- add: cone\n", "line 14: cannot add 'cone'"),
+        (
+            "\n- add: sphere\n  material:\n    colour: [1, 0, 0]\n",
+            "line 16: unknown material property 'colour'",
+        ),
+        (
+            "\n- add: sphere\n  material:\n    color: [1, 0]\n",
+            "line 16: expected 3 numbers, found 2",
+        ),
+        (
+            "\n- add: sphere\n  transform:\n    - [rotate-x, fast]\n",
+            "line 16: expected a number, found 'fast'",
+        ),
+        (
+            "\n- add: sphere\n  transform:\n    - [twist, 1]\n",
+            "line 16: unknown transform 'twist'",
+        ),
+        (
+            "\n- add: sphere\n  material: plaid\n",
+            "line 15: unknown material 'plaid'",
+        ),
+        (
+            "\n- add: sphere\n  transform:\n    - big\n",
+            "line 16: 'big' has not been defined",
+        ),
+        (
+            "\n- add: sphere\n  transform:\n    - [scale, 0, 1, 1]\n",
+            "line 16: 'scale' flattens space and cannot be undone",
+        ),
+        (
+            "\n- add: light\n  at: [0, 0, 0]\n  intensity: [1, 1, 1]\n",
+            "line 14: scene already has a light",
+        ),
+    ];
+
+    for (items, expected) in cases {
+        let error = scene_with(items).err().unwrap();
+        assert_eq!(error.to_string(), expected);
+    }
+}
+
+#[test]
+fn transforms_defined_in_terms_of_themselves_are_reported() {
+    let error = scene_with(
+        "
+- define: t
+  value: [t]
+
+- add: sphere
+  transform: [t]
+",
+    )
+    .err()
+    .unwrap();
+
+    assert_eq!(
+        error.to_string(),
+        "line 15: 't' is defined in terms of itself"
+    );
+}
+
+#[test]
+fn materials_defined_in_terms_of_themselves_are_reported() {
+    let error = scene_with(
+        "
+- define: m
+  value: m
+
+- add: sphere
+  material: m
+",
+    )
+    .err()
+    .unwrap();
+
+    assert_eq!(
+        error.to_string(),
+        "line 15: 'm' is defined in terms of itself"
+    );
+}
+
+#[test]
+fn defines_that_loop_through_each_other_are_reported() {
+    let error = scene_with(
+        "
+- define: a
+  value: [[scale, 2, 2, 2]]
+
+- define: b
+  value: [a]
+
+- define: a
+  value: [b]
+
+- add: sphere
+  transform: [a]
+",
+    )
+    .err()
+    .unwrap();
+
+    assert_eq!(
+        error.to_string(),
+        "line 18: 'a' is defined in terms of itself"
+    );
+}
+
+#[test]
+fn yaml_syntax_errors_carry_line_numbers() {
+    let error = parse_scene("- add: camera\n  width: [1, 2\n- add: light\n")
+        .err()
+        .unwrap();
+
+    assert!(error.line.is_some());
+    assert!(!error.message.contains("at line"));
+}
+
+#[test]
+fn example_scene_loads() {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../cli/scenes/reflections.yaml"
+    );
+    let scene = load_scene(path).unwrap();
+
+    assert_eq!(scene.world.objects.len(), 5);
+    assert_eq!(scene.camera.hsize, 400);
+}
+
+#[test]
+fn unreadable_files_are_reported() {
+    let error = load_scene("no/such/scene.yaml").err().unwrap();
+
+    assert_eq!(error.line, None);
+    assert!(error.message.starts_with("cannot read no/such/scene.yaml"));
+}